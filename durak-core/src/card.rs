@@ -133,16 +133,13 @@ pub fn hand_fmt(hand: &[Card]) -> String {
     hand.iter().map(|c| format!("{:>4}",format!("{}",c))).collect::<String>()
 }
 
-pub(crate) fn transfer_card(v_from: &mut Vec<Card>, v_to: &mut Vec<Card>, card: &Card) {
-    let mut ind = 0;
-    while ind < v_from.len() && v_from[ind] != *card { ind += 1};
-    if ind < v_from.len() {
-        v_to.push(v_from.remove(ind));
-    }
+pub(crate) fn remove_card(v_from: &mut Vec<Card>, card: &Card) -> Option<Card> {
+    let ind = v_from.iter().position(|c| c == card)?;
+    Some(v_from.remove(ind))
 }
 
 /// Sorts cards with preference given to the trump suit.
-pub fn sort_cards(cards: &mut Vec<Card>, trump: Suit) {
+pub fn sort_cards(cards: &mut [Card], trump: Suit) {
     cards.sort_by_key(|&card| {
         let val = usize::try_from(card).unwrap();
         match card.suit {
@@ -0,0 +1,38 @@
+//! Errors returned by the game engine.
+
+use thiserror::Error;
+
+use crate::toplaystate::ValidationError;
+
+/// Why a game could not be set up or could not be played to the end.
+#[derive(Error,Debug)]
+pub enum DurakError {
+    /// A player failed while joining the game, before it had an ID.
+    #[error("A player failed to join the game: {0}")]
+    JoinError(#[source] anyhow::Error),
+    /// A player returned an error, e.g. a network player that disconnected.
+    #[error("Player {id} failed: {source}")]
+    PlayerError {
+        /// ID of the player that failed.
+        id: u64,
+        /// The error the player returned.
+        source: anyhow::Error,
+    },
+    /// A player made a move that is against the rules.
+    #[error("Player {id} made an invalid move: {source}")]
+    InvalidMove {
+        /// ID of the player that made the move.
+        id: u64,
+        /// Why the move is invalid.
+        source: ValidationError,
+    },
+    /// A player tried to join with an ID that is already taken.
+    #[error("Duplicate player id {0}")]
+    DuplicateId(u64),
+    /// The game can't be played with these players and rules, e.g. too few players.
+    #[error("Invalid game setup: {0}")]
+    Config(String),
+    /// The engine got into a state the rules don't allow. This is a bug in the engine.
+    #[error("Internal engine error: {0}")]
+    Internal(String),
+}
@@ -1,22 +1,104 @@
 //! The core game engine.
 
 use std::borrow::Cow;
+use std::fmt;
+use std::time::Duration;
 
-use anyhow::{bail,Result};
+use anyhow::Result;
 use async_trait::async_trait;
 use rand::Rng;
-use tracing::{debug,error};
+use rand::seq::SliceRandom;
+use tracing::{debug,warn,error};
 use serde::{Serialize,Deserialize};
 
 use crate::prelude::*;
-use crate::card::transfer_card;
+use crate::card::remove_card;
+use crate::toplaystate::ValidationError;
+
+/// How long a player gets to observe a move before the game goes on without them, by default.
+pub const OBSERVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A round limit for games between bots, see [`DurakGame::with_round_limit()`]. Deterministic bots
+/// can otherwise pass the same cards back and forth forever once the talon is empty.
+pub const BOT_ROUND_LIMIT: usize = 500;
 
 /// Defines the actions available to a player on attack and defense turns.
 #[allow(missing_docs)]
-#[derive(PartialEq,Serialize,Deserialize)]
+#[derive(PartialEq,Clone,Debug,Serialize,Deserialize)]
 pub enum Action {
+    /// Attack with a card.
     Play(Card),
+    /// Attack with several cards of the same rank at once.
+    PlayMany(Vec<Card>),
+    /// Beat the unbeaten attack card `against` with `card`.
+    Defend { card: Card, against: Card },
     Pass,
+    /// Leave the game, see [`Rules::leave`]. Allowed on attack and defense turns.
+    Forfeit,
+    /// Take back your last attack or defense, see [`DurakGame::with_takeback()`]. Allowed on
+    /// attack and defense turns when [`ToPlayState::takeback`] is set.
+    Undo,
+}
+
+impl Action {
+    /// Builds an attack out of the selected cards: [`Action::Pass`] for none, [`Action::Play`] for
+    /// one and [`Action::PlayMany`] for more.
+    pub fn attack_with(mut cards: Vec<Card>) -> Action {
+        match cards.len() {
+            0 => Action::Pass,
+            1 => Action::Play(cards.remove(0)),
+            _ => Action::PlayMany(cards),
+        }
+    }
+}
+
+/// Something that happened in the game, passed to [`DurakPlayer::observe_move()`] so players don't
+/// have to work it out from the state. Players are named by id.
+#[derive(PartialEq,Clone,Debug,Serialize,Deserialize)]
+pub enum MoveEvent {
+    /// A player attacked with a card.
+    Attacked(u64,Card),
+    /// The defender beat an attack with a card.
+    Defended(u64,Card),
+    /// An attacker passed, or the defender gave up and will take the cards.
+    Passed(u64),
+    /// A player piled cards on for the defender to take.
+    PiledOn(u64,Vec<Card>),
+    /// The defender took this many cards from the table.
+    TookCards(u64,usize),
+    /// The defender beat every attack and the table was discarded.
+    RoundDiscarded,
+    /// A player drew this many cards from the talon.
+    Drew(u64,usize),
+    /// A player has run out of cards and is safely out of the game.
+    PlayerOut(u64),
+    /// A player left the game, see [`Rules::leave`].
+    Forfeited(u64),
+    /// A player took back their last move, and the game went back to just before it.
+    TookBack(u64),
+    /// A player swapped the trump six for the face up trump card, see [`Rules::trump_exchange`].
+    ExchangedTrump(u64,Card,Card),
+    /// The first attacker showed the lowest trump dealt, see [`Rules::first_attacker`].
+    ShowedLowestTrump(u64,Card),
+}
+
+impl fmt::Display for MoveEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveEvent::Attacked(id,card) => write!(f,"Player {} attacked with the {}",id,card),
+            MoveEvent::Defended(id,card) => write!(f,"Player {} defended with the {}",id,card),
+            MoveEvent::Passed(id) => write!(f,"Player {} passed",id),
+            MoveEvent::PiledOn(id,cards) => write!(f,"Player {} piled on{}",id,hand_fmt(cards)),
+            MoveEvent::TookCards(id,n) => write!(f,"Player {} took {} cards",id,n),
+            MoveEvent::RoundDiscarded => write!(f,"The table was discarded"),
+            MoveEvent::Drew(id,n) => write!(f,"Player {} drew {} cards",id,n),
+            MoveEvent::PlayerOut(id) => write!(f,"Player {} is out",id),
+            MoveEvent::Forfeited(id) => write!(f,"Player {} left the game",id),
+            MoveEvent::TookBack(id) => write!(f,"Player {} took back their last move",id),
+            MoveEvent::ExchangedTrump(id,six,face_up) => write!(f,"Player {} exchanged the {} for the {}",id,six,face_up),
+            MoveEvent::ShowedLowestTrump(id,card) => write!(f,"Player {} attacks first with the lowest trump, the {}",id,card),
+        }
+    }
 }
 
 /// Used to signify if the player is ready for another game.
@@ -27,6 +109,42 @@ pub enum Ready {
     No,
 }
 
+/// What the engine does when a player makes a move that is against the rules.
+#[derive(Copy,Clone,PartialEq,Eq,Debug,Default,Serialize,Deserialize)]
+pub enum IllegalMovePolicy {
+    /// End the game with a [`DurakError::InvalidMove`].
+    #[default]
+    Abort,
+    /// Tell the player what was wrong with [`DurakPlayer::message()`] and ask again, up to this
+    /// many times. After that the player passes.
+    Reprompt(usize),
+    /// Tell the player what was wrong and pass for them: an attacker stops attacking, a defender
+    /// takes and a player piling on adds nothing.
+    AutoPass,
+    /// The player forfeits, as if they had played [`Action::Forfeit`].
+    Forfeit,
+}
+
+/// How players are seated when the game is initialized. Play goes round the seats in order.
+#[derive(Copy,Clone,PartialEq,Eq,Debug,Default,Serialize,Deserialize)]
+pub enum SeatingPolicy {
+    /// In the order they were added to the game.
+    #[default]
+    JoinOrder,
+    /// In a random order.
+    Random,
+    /// Each player is asked for a seat with [`DurakPlayer::choose_seat()`] when they join. Seats
+    /// go to whoever asked first, everyone else fills the empty seats in join order.
+    Chosen,
+}
+
+// what to do after a player made an invalid move
+enum Retry {
+    Ask,
+    Pass,
+    Forfeit,
+}
+
 /// Trait defining player behavior.
 /// Implement this when making a player client.
 #[async_trait]
@@ -41,15 +159,58 @@ pub trait DurakPlayer: Send + Sync {
     async fn pile_on(&mut self, state: &ToPlayState) -> Result<Vec<Card>>;
 
     /// Not playing a turn, but is sent whenever another player plays a turn to update player
-    /// client game state.
-    async fn observe_move(&mut self, state: &ToPlayState) -> Result<()> {
-        _ = state;
+    /// client game state, along with what happened since the last one. Every player observes at the
+    /// same time, an error or taking longer than the game's observe timeout is logged and the game
+    /// goes on. The timeout can only fire while the player is awaiting, so blocking work such as
+    /// reading from a socket belongs in [`tokio::task::spawn_blocking()`].
+    async fn observe_move(&mut self, state: &ToPlayState, events: &[MoveEvent]) -> Result<()> {
+        _ = (state,events);
         Ok(())
     }
 
-    /// Returns a unique ID for the player.
-    /// [`PlayerInfo`] contains all the other player's IDs. No duplicates allowed.
-    async fn get_id(&mut self, player_info: &Vec<PlayerInfo>) -> Result<u64>;
+    /// Offered at the start of a round to the holder of the six of trumps when
+    /// [`Rules::trump_exchange`] is on. Return true to swap it for the face up trump card at the
+    /// bottom of the talon. `state.to_play` is whoever leads the round.
+    async fn exchange_trump(&mut self, state: &ToPlayState) -> Result<bool> {
+        _ = state;
+        Ok(false)
+    }
+
+    /// Returns the ID the player would like, which it gets if no one else has it yet.
+    /// [`PlayerInfo`] contains all the other player's IDs. The engine hands out IDs now and tells
+    /// the player with [`DurakPlayer::seated()`], so this is going away.
+    #[deprecated(note = "the engine assigns IDs, see DurakPlayer::seated()")]
+    async fn get_id(&mut self, player_info: &[PlayerInfo]) -> Result<u64> {
+        Ok(player_info.iter().map(|info| info.id + 1).max().unwrap_or(0))
+    }
+
+    /// The join handshake. `player_info` lists everyone who joined before. Returns how the player
+    /// would like to be shown to the others, the engine makes sure the name is unique.
+    async fn join(&mut self, player_info: &[PlayerInfo]) -> Result<Profile> {
+        _ = player_info;
+        Ok(Profile::default())
+    }
+
+    /// Whether the player is playing over the network. Moves can't be taken back in games with
+    /// remote players.
+    fn is_remote(&self) -> bool {
+        false
+    }
+
+    /// Asked after joining a game where players choose their seats, see [`SeatingPolicy::Chosen`].
+    /// Returns the seat the player would like out of `seats`, counting from 0, or `None` for any
+    /// seat.
+    async fn choose_seat(&mut self, player_info: &[PlayerInfo], seats: usize) -> Result<Option<usize>> {
+        _ = (player_info,seats);
+        Ok(None)
+    }
+
+    /// Sent when the game starts, with the ID and seat the engine gave the player and the rules
+    /// of the game. The seat indexes [`ToPlayState::player_info`].
+    async fn seated(&mut self, id: u64, seat: usize, rules: &Rules) -> Result<()> {
+        _ = (id,seat,rules);
+        Ok(())
+    }
 
     /// A notification that the player has lost the game.
     async fn lost(&mut self) -> Result<Ready> {
@@ -68,6 +229,12 @@ pub trait DurakPlayer: Send + Sync {
         Ok(())
     }
 
+    /// A notification that the game ended in a draw, without a durak. Defaults to
+    /// [`DurakPlayer::won()`].
+    async fn draw(&mut self) -> Result<Ready> {
+        self.won().await
+    }
+
     /// A notification that there has been some error and the game engine is shutting down.
     async fn error(&mut self, error: &str) -> Result<()> {
         _ = error;
@@ -75,45 +242,144 @@ pub trait DurakPlayer: Send + Sync {
     }
 }
 
+#[derive(Copy,Clone,PartialEq,Debug)]
+enum Outcome {
+    Won,
+    Lost,
+    Draw,
+}
+
+#[derive(Clone)]
 struct Player {
     id: u64,
+    profile: Profile,
     hand: Vec<Card>,
 }
 
-#[derive(PartialEq)]
+#[derive(Clone,PartialEq,Debug)]
 enum GameTurnType {
     Attack,
     Defense,
     PileOn,
-    EndRound,
+    EndRound(RoundEnd),
     GameEnd,
 }
 
+// how a round finished
+#[derive(Copy,Clone,PartialEq,Debug)]
+enum RoundEnd {
+    Defended, // every attack was beaten, the table is discarded and the defender leads next
+    Taken, // the defender picks up the table and the player after them leads next
+}
+
+// what just happened in a round, moves the round on through GameState::advance()
+#[derive(Copy,Clone,PartialEq,Debug)]
+enum RoundEvent {
+    Attacked,
+    AttackerPassed,
+    Beat,
+    DefenderTook,
+    PiledOn,
+}
+
+#[derive(Clone)]
 struct GameState {
     trump: Suit,
+    trump_card: Card,
     players: Vec<Player>,
     attackers: Vec<usize>, // indices for attackers for current round
     attackers_passed: Vec<usize>, // indices for attackers who have passed since last attack
     draw_pile: Vec<Card>,
-    attack_cards: Vec<Card>,
-    defense_cards: Vec<Card>,
+    table: Table,
     discarded_cards: Vec<Card>,
     defender: usize,
     last_attacker: usize, // the last attacker (used for reference during defense turns)
     to_play: usize, // whoever's turn it currently is
     turn_type: GameTurnType,
+    rules: Rules,
+    round_limit: Option<usize>,
+    illegal_moves: IllegalMovePolicy,
+    observe_timeout: Duration, // how long a player gets to observe a move before the game goes on
+    forfeited: Option<usize>, // seat of a player who forfeited, which ends the game
+    left: Vec<usize>, // seats of players who left the game and had their cards discarded
+    first_attack_reveal: Option<Card>, // lowest trump shown by the first attacker, until announced
+    events: Vec<MoveEvent>, // what has happened since players last observed a move
+    takeback: bool, // whether players may take back moves
+    undo: Option<usize>, // seat of a player asking to take back their last move
+    stats: GameStats,
 }
 
 /// The durak game engine.
 pub struct DurakGame {
     state: GameState,
     engines: Vec<Box<dyn DurakPlayer>>,
+    seating: SeatingPolicy,
+    seats: usize, // the number of seats players choose from
+    seat_requests: Vec<Option<usize>>, // the seat each player asked for, in join order
+    history: Vec<GameState>, // the state at the start of each turn, when taking back moves
+}
+
+/// Summary statistics about a game, useful for simulations.
+#[derive(Serialize,Deserialize,Clone,Debug)]
+pub struct GameStats {
+    /// Trump suit.
+    pub trump: Suit,
+    /// Number of completed rounds.
+    pub rounds: usize,
+    /// Seat index of the player who attacked first.
+    pub first_attacker: usize,
+    /// Number of trump cards each seat was dealt at the start of the game.
+    pub initial_trumps: Vec<usize>,
+    /// Seat index of the durak, if there was one.
+    pub durak: Option<usize>,
+    /// Seat indices in the order the players got rid of all their cards. The durak is not
+    /// included.
+    pub finish_order: Vec<usize>,
+    /// Player ID for each seat.
+    pub player_ids: Vec<u64>,
+    /// Team for each seat, when playing in teams.
+    pub teams: Option<Vec<usize>>,
+    /// The team that lost, when playing in teams.
+    pub losing_team: Option<usize>,
+    /// Whether the game ended without a loser, e.g. when the last two players got rid of their
+    /// cards in the same round.
+    pub draw: bool,
+    /// Seat index of a player who forfeited the game, they are also the durak.
+    pub forfeited: Option<usize>,
+    /// Seat indices of players who left the game and had their cards discarded. They lost, but
+    /// the game went on without them.
+    pub left: Vec<usize>,
 }
 
 /// The results of a game of Durak
 pub struct DurakGameResult {
-    winner: Option<(Box<dyn DurakPlayer>,Ready)>,
+    winners: Vec<(Box<dyn DurakPlayer>,Ready)>,
     losers: Vec<(Box<dyn DurakPlayer>,Ready)>,
+    drawn: Vec<(Box<dyn DurakPlayer>,Ready)>,
+    stats: GameStats,
+}
+
+impl DurakGameResult {
+    /// Summary statistics for the finished game.
+    pub fn stats(&self) -> &GameStats {
+        &self.stats
+    }
+
+    /// The team that lost, when playing in teams.
+    pub fn losing_team(&self) -> Option<usize> {
+        self.stats.losing_team
+    }
+
+    /// Whether the game ended without a loser.
+    pub fn is_draw(&self) -> bool {
+        self.stats.draw
+    }
+}
+
+impl Default for DurakGame {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DurakGame {
@@ -122,55 +388,170 @@ impl DurakGame {
         DurakGame {
             state: GameState::new(),
             engines: Vec::new(),
+            seating: SeatingPolicy::JoinOrder,
+            seats: 6,
+            seat_requests: Vec::new(),
+            history: Vec::new(),
         }
     }
 
-    /// Add a player to the game. Will call [`DurakPlayer::get_id()`] so make sure player client is
-    /// initialized first.
-    pub async fn add_player(&mut self, mut engine: Box<dyn DurakPlayer>) -> Result<()> {
-        let id = engine.get_id(&get_player_info(&self.state)).await?;
-        self.state.add_player(id)?;
+    /// Sets the house rules to play with.
+    pub fn with_rules(mut self, rules: Rules) -> Self {
+        self.state.rules = rules;
+        self
+    }
+
+    /// The house rules the game is played with.
+    pub fn rules(&self) -> &Rules {
+        &self.state.rules
+    }
+
+    /// Ends the game without a durak after `rounds` rounds. Useful for bots that might otherwise
+    /// play forever.
+    pub fn with_round_limit(mut self, rounds: usize) -> Self {
+        self.state.round_limit = Some(rounds);
+        self
+    }
+
+    /// Sets what happens when a player makes a move that is against the rules. By default the
+    /// game is aborted.
+    pub fn with_illegal_move_policy(mut self, policy: IllegalMovePolicy) -> Self {
+        self.state.illegal_moves = policy;
+        self
+    }
+
+    /// Sets how long each player gets to observe a move, see [`DurakPlayer::observe_move()`].
+    /// Players observe each move all at once, and one that fails or runs out of time only misses
+    /// out on that move. Defaults to [`OBSERVE_TIMEOUT`].
+    pub fn with_observe_timeout(mut self, timeout: Duration) -> Self {
+        self.state.observe_timeout = timeout;
+        self
+    }
+
+    /// Sets how players are seated, in join order by default. Must be set before adding players
+    /// for them to be asked to choose a seat.
+    pub fn with_seating(mut self, seating: SeatingPolicy) -> Self {
+        self.seating = seating;
+        self
+    }
+
+    /// Sets how many seats players choose from with [`SeatingPolicy::Chosen`], usually the number
+    /// of players expected. Defaults to six, the most a game can have. Players asking for a seat
+    /// that stays empty are seated like those who didn't choose.
+    pub fn with_seats(mut self, seats: usize) -> Self {
+        self.seats = seats;
+        self
+    }
+
+    /// Lets players take back their last attack or defense with [`Action::Undo`], e.g. when
+    /// practising against bots. The game goes back to just before the move and everyone plays on
+    /// from there. Always off when any player is remote, see [`DurakPlayer::is_remote()`].
+    pub fn with_takeback(mut self, takeback: bool) -> Self {
+        self.state.takeback = takeback;
+        self
+    }
+
+    /// Add a player to the game. Will call [`DurakPlayer::join()`] so make sure player client is
+    /// initialized first. The player gets the ID it asks for with [`DurakPlayer::get_id()`] if it's
+    /// free, or the next free one otherwise.
+    pub async fn add_player(&mut self, mut engine: Box<dyn DurakPlayer>) -> Result<(),DurakError> {
+        let player_info = get_player_info(&self.state);
+        #[allow(deprecated)]
+        let preferred = engine.get_id(&player_info).await.map_err(DurakError::JoinError)?;
+        let id = match player_info.iter().any(|info| info.id == preferred) {
+            true => player_info.iter().map(|info| info.id + 1).max().unwrap_or(0),
+            false => preferred,
+        };
+        self.state.check_new_player(id)?;
+        let profile = engine.join(&player_info).await.map_err(DurakError::JoinError)?;
+        let seat = match self.seating {
+            SeatingPolicy::Chosen => engine.choose_seat(&player_info,self.seats).await.map_err(DurakError::JoinError)?,
+            _ => None,
+        };
+        self.state.add_player(id,profile)?;
+        self.seat_requests.push(seat);
         self.engines.push(engine);
         debug!("Added player # {}", id);
         Ok(())
     }
 
+    /// Info for the players that have been added so far.
+    pub fn player_info(&self) -> Vec<PlayerInfo> {
+        get_player_info(&self.state)
+    }
+
+    /// Sends a message to every player. Useful before the game starts, e.g. in a lobby.
+    pub async fn message_all(&mut self, msg: &str) -> Result<(),DurakError> {
+        for (i,engine) in self.engines.iter_mut().enumerate() {
+            engine.message(msg).await.map_err(self.state.player_failed(i))?;
+        }
+        Ok(())
+    }
+
     /// Initialize the game. Deals cards to players and decides what the trump suit is based on
     /// RNG.
-    pub fn init<R: Rng>(&mut self, rng: &mut R) -> Result<()> {
+    pub fn init<R: Rng>(&mut self, rng: &mut R) -> Result<(),DurakError> {
+        self.seat_players(rng);
         self.state.init(rng)
     }
 
-    /// Start the game.
-    pub async fn run_game(mut self) -> Result<DurakGameResult> {
-        let mut result = DurakGameResult {
-            winner: None,
-            losers: vec![],
+    // moves players from join order into their seats
+    fn seat_players<R: Rng>(&mut self, rng: &mut R) {
+        let num_players = self.engines.len();
+        // the join index of whoever sits in each seat
+        let order: Vec<usize> = match self.seating {
+            SeatingPolicy::JoinOrder => return,
+            SeatingPolicy::Random => {
+                let mut unseated: Vec<usize> = (0..num_players).collect();
+                (0..num_players).map(|_| unseated.swap_remove(rng.gen_range(0..unseated.len()))).collect()
+            },
+            SeatingPolicy::Chosen => {
+                let mut seats: Vec<Option<usize>> = vec![None; num_players];
+                for (ind,&request) in self.seat_requests.iter().enumerate() {
+                    if let Some(seat) = request.filter(|&seat| seat < num_players && seats[seat].is_none()) {
+                        seats[seat] = Some(ind);
+                    }
+                }
+                let mut unseated = (0..num_players).filter(|ind| !seats.contains(&Some(*ind))).collect::<Vec<_>>().into_iter();
+                seats.into_iter().map(|seat| seat.or_else(|| unseated.next()).unwrap()).collect()
+            },
         };
+        self.engines = reorder(std::mem::take(&mut self.engines),&order);
+        self.state.players = reorder(std::mem::take(&mut self.state.players),&order);
+        self.seat_requests = reorder(std::mem::take(&mut self.seat_requests),&order);
+    }
+
+    /// Start the game.
+    pub async fn run_game(mut self) -> Result<DurakGameResult,DurakError> {
         let mut set = tokio::task::JoinSet::new();
         match self.game_loop().await {
             Ok(()) => {
-                // notify players of win/lost status
-                for (player, mut engine) in std::iter::zip(self.state.players,self.engines) {
-                    if player.hand.len() == 0 {
-                        debug!("Player {} won ", player.id);
-                        set.spawn( async move {
-                            engine.won().await.map(|ready| (player,engine,ready))
-                        });
-                    } else {
-                        debug!("Player {} lost ", player.id);
-                        set.spawn( async move {
-                            engine.lost().await.map(|ready| (player,engine,ready))
-                        });
-                    }
+                let outcomes = self.state.finish();
+                let mut result = DurakGameResult {
+                    winners: Vec::new(),
+                    losers: Vec::new(),
+                    drawn: Vec::new(),
+                    stats: self.state.stats.clone(),
+                };
+                // notify players of win/lost/draw status
+                for ((player, mut engine), outcome) in std::iter::zip(self.state.players,self.engines).zip(outcomes) {
+                    debug!("Player {} {:?}", player.id, outcome);
+                    set.spawn( async move {
+                        let ready = match outcome {
+                            Outcome::Won => engine.won().await,
+                            Outcome::Lost => engine.lost().await,
+                            Outcome::Draw => engine.draw().await,
+                        };
+                        ready.map(|ready| (outcome,engine,ready))
+                    });
                 }
                 while let Some(res) = set.join_next().await {
                     match res {
-                        Ok(Ok((player,engine,ready))) => {
-                            if player.hand.len() == 0 {
-                                result.winner = Some((engine,ready));
-                            } else {
-                                result.losers.push((engine,ready));
+                        Ok(Ok((outcome,engine,ready))) => {
+                            match outcome {
+                                Outcome::Won => result.winners.push((engine,ready)),
+                                Outcome::Lost => result.losers.push((engine,ready)),
+                                Outcome::Draw => result.drawn.push((engine,ready)),
                             }
                         },
                         Ok(Err(e)) => { error!("Error: {}", e); },
@@ -180,10 +561,10 @@ impl DurakGame {
                 Ok(result)
             },
             Err(e) => {
-                for (player, mut engine) in std::iter::zip(self.state.players,self.engines) {
+                for mut engine in self.engines {
                     let err_str = format!("{}",e); 
                     set.spawn( async move {
-                        engine.error(&err_str).await.map(|_| (player,engine,Ready::No))
+                        engine.error(&err_str).await.map(|_| (Outcome::Lost,engine,Ready::No))
                     });
                 }
                 while let Some(res) = set.join_next().await {
@@ -197,57 +578,320 @@ impl DurakGame {
         }
     }
 
-    async fn game_loop(&mut self) -> Result<()> {
+    async fn game_loop(&mut self) -> Result<(),DurakError> {
+        for (seat,engine) in self.engines.iter_mut().enumerate() {
+            engine.seated(self.state.players[seat].id,seat,&self.state.rules).await.map_err(self.state.player_failed(seat))?;
+        }
+        if let Some(card) = self.state.first_attack_reveal.take() {
+            let event = MoveEvent::ShowedLowestTrump(self.state.players[self.state.to_play].id,card);
+            debug!("{}",event);
+            self.state.broadcast(&mut self.engines,None,&[event]).await;
+        }
+        if self.state.takeback && self.engines.iter().any(|engine| engine.is_remote()) {
+            debug!("Not allowing moves to be taken back in a network game");
+            self.state.takeback = false;
+        }
         while self.state.turn_type != GameTurnType::GameEnd {
+            if self.state.takeback { self.history.push(self.state.clone()); }
             self.state.play_turn(&mut self.engines).await?;
-            for (i,engine) in self.engines.iter_mut().enumerate() {
-                let to_play_state = gen_to_play_state_w_hand(&self.state,i);
-                engine.observe_move(&to_play_state).await?;
-            }
+            if let Some(seat) = self.state.undo.take() { self.take_back(seat).await; }
+            self.state.check_cards()?;
+            let events = std::mem::take(&mut self.state.events);
+            self.state.broadcast(&mut self.engines,None,&events).await;
         }
         Ok(())
     }
+
+    // goes back to the start of the last turn the player in `seat` attacked or defended on
+    async fn take_back(&mut self, seat: usize) {
+        // the turn that asked for it didn't play a move
+        self.history.pop();
+        let id = self.state.players[seat].id;
+        match self.history.iter().rposition(|snapshot| snapshot.prompts(seat)) {
+            Some(ind) => {
+                debug!("Player # {} took back their last move",id);
+                self.history.truncate(ind + 1);
+                self.state = self.history.pop().unwrap();
+                self.state.events.push(MoveEvent::TookBack(id));
+            },
+            None => {
+                if let Err(e) = self.engines[seat].message("There is no move to take back").await {
+                    warn!("Player # {} failed to get a message: {}",id,e);
+                }
+            },
+        }
+    }
+}
+
+/// The kinds of move a player is asked for, see [`Playout::turn()`].
+#[derive(Copy,Clone,PartialEq,Eq,Debug)]
+pub enum Turn {
+    /// An attack, as with [`DurakPlayer::attack()`].
+    Attack,
+    /// A defense, as with [`DurakPlayer::defend()`].
+    Defense,
+    /// Cards for the defender to take, as with [`DurakPlayer::pile_on()`].
+    PileOn,
+}
+
+/// A game played out move by move without any players, for bots that search ahead. It starts from
+/// what one player can see on their turn, with the cards they can't see dealt at random. The trump
+/// exchange is never offered and nobody leaves.
+#[derive(Clone)]
+pub struct Playout {
+    state: GameState,
+    pile_on: Vec<usize>, // attackers still to pile on this round, in order
+}
+
+impl Playout {
+    /// Deals out the game `view` shows the player asked for a `turn`, played with `rules`.
+    /// `known` has the cards known to be in each seat's hand, e.g. cards they took, and `discarded`
+    /// the discarded cards. The other cards nobody can see are shuffled into the hands and the
+    /// talon.
+    pub fn deal<R: Rng>(view: &ToPlayState, turn: Turn, rules: &Rules, known: &[Vec<Card>], discarded: &[Card], rng: &mut R) -> Self {
+        let seat = view.to_play;
+        let num_players = view.player_info.len();
+        // the face up trump stays at the bottom of the talon until it's drawn
+        let face_up = (view.draw_pile_len > 0).then_some(view.trump_card);
+        let mut seen: Vec<Card> = view.hand.iter().chain(view.table.cards()).chain(discarded).chain(&face_up).copied().collect();
+        let mut hands: Vec<Vec<Card>> = vec![Vec::new(); num_players];
+        hands[seat] = view.hand.to_vec();
+        for (ind,cards) in known.iter().enumerate().filter(|&(ind,_)| ind != seat && ind < num_players) {
+            for &card in cards {
+                if hands[ind].len() < view.player_info[ind].hand_len && !seen.contains(&card) {
+                    hands[ind].push(card);
+                    seen.push(card);
+                }
+            }
+        }
+        let mut unseen: Vec<Card> = (0..36).filter_map(|i| Card::try_from(i).ok()).filter(|card| !seen.contains(card)).collect();
+        unseen.shuffle(rng);
+        for (hand,info) in hands.iter_mut().zip(view.player_info.iter()) {
+            while hand.len() < info.hand_len {
+                let Some(card) = unseen.pop() else { break; };
+                hand.push(card);
+            }
+        }
+        let mut draw_pile: Vec<Card> = face_up.into_iter().collect();
+        draw_pile.extend(unseen.drain(..unseen.len().min(view.draw_pile_len.saturating_sub(1))));
+
+        let mut state = GameState::new();
+        state.rules = rules.clone();
+        state.trump = view.trump;
+        state.trump_card = view.trump_card;
+        state.players = view.player_info.iter().zip(hands).map(|(info,hand)| Player { id: info.id, profile: info.profile.clone(), hand }).collect();
+        state.draw_pile = draw_pile;
+        state.table = view.table.clone().into_owned();
+        // whatever is left over must have been discarded
+        state.discarded_cards = discarded.iter().copied().chain(unseen).collect();
+        state.defender = view.defender;
+        // the first attacker of the round sits before the defender
+        state.to_play = (1..num_players)
+            .map(|i| (view.defender + num_players - i) % num_players)
+            .find(|&ind| !state.players[ind].hand.is_empty() && !state.teammates(ind,view.defender))
+            .unwrap_or(seat);
+        state.seat_attackers();
+        // whoever attacked this round or is asked to attack is an attacker, even with no cards left
+        for ind in [view.last_attacker,seat] {
+            if ind != view.defender && !state.attackers.contains(&ind) && (ind == seat || !view.table.is_empty()) { state.attackers.push(ind); }
+        }
+        state.last_attacker = view.last_attacker;
+        state.to_play = seat;
+        state.turn_type = match turn {
+            Turn::Attack => GameTurnType::Attack,
+            Turn::Defense => GameTurnType::Defense,
+            Turn::PileOn => GameTurnType::PileOn,
+        };
+        state.stats.finish_order = (0..num_players).filter(|&ind| state.draw_pile.is_empty() && state.players[ind].hand.is_empty()).collect();
+        let pile_on = match turn {
+            Turn::PileOn => state.attackers.iter().copied().skip_while(|&ind| ind != seat).collect(),
+            _ => Vec::new(),
+        };
+        Playout { state, pile_on }
+    }
+
+    /// The seat to move next and the kind of move, or `None` once the game is over.
+    pub fn turn(&self) -> Option<(usize,Turn)> {
+        match self.state.turn_type {
+            GameTurnType::Attack => Some((self.state.to_play,Turn::Attack)),
+            GameTurnType::Defense => Some((self.state.to_play,Turn::Defense)),
+            GameTurnType::PileOn => Some((self.state.to_play,Turn::PileOn)),
+            _ => None,
+        }
+    }
+
+    /// What the player to move can see.
+    pub fn view(&self) -> ToPlayState<'_> {
+        gen_to_play_state(&self.state)
+    }
+
+    /// Plays a move for the player to move. Attacks and defenses are the same as on their turn in
+    /// a game, pile ons are [`Action::Play`] or [`Action::PlayMany`], and [`Action::Pass`] for
+    /// none. [`Action::Forfeit`] and [`Action::Undo`] are never allowed.
+    pub fn play(&mut self, action: Action) -> Result<(),DurakError> {
+        let seat = self.state.to_play;
+        let view = gen_to_play_state(&self.state);
+        let valid = match (&self.state.turn_type,&action) {
+            (_,Action::Forfeit | Action::Undo) => Err(ValidationError::WrongTurnType),
+            (GameTurnType::Attack | GameTurnType::Defense | GameTurnType::PileOn,Action::Pass) => Ok(()),
+            (GameTurnType::Attack,_) => view.validate_attack(&action),
+            (GameTurnType::Defense,_) => view.validate_defense(&action),
+            (GameTurnType::PileOn,Action::Play(card)) => view.validate_pile_on(&[*card]),
+            (GameTurnType::PileOn,Action::PlayMany(cards)) => view.validate_pile_on(cards),
+            _ => Err(ValidationError::WrongTurnType),
+        };
+        valid.map_err(self.state.invalid_move(seat))?;
+        match self.state.turn_type {
+            GameTurnType::Attack => self.state.apply_attack(action)?,
+            GameTurnType::Defense => {
+                self.state.apply_defense(action)?;
+                if self.state.turn_type == GameTurnType::PileOn { self.pile_on = self.state.attackers.clone(); }
+            },
+            _ => {
+                let cards = match action {
+                    Action::Play(card) => vec![card],
+                    Action::PlayMany(cards) => cards,
+                    _ => Vec::new(),
+                };
+                self.state.apply_pile_on(seat,cards)?;
+                self.pile_on.retain(|&ind| ind != seat);
+            },
+        }
+        self.settle()
+    }
+
+    // plays on until someone has a choice to make or the game is over
+    fn settle(&mut self) -> Result<(),DurakError> {
+        // nobody observes a playout
+        self.state.events.clear();
+        loop {
+            match self.state.turn_type {
+                GameTurnType::Attack if !self.state.may_attack(self.state.to_play) || self.state.attack_room() == 0 => {
+                    self.state.advance(RoundEvent::AttackerPassed)?;
+                },
+                GameTurnType::PileOn => {
+                    // attackers who ran out of cards have nothing to pile on
+                    self.pile_on.retain(|&ind| !self.state.players[ind].hand.is_empty());
+                    match self.pile_on.first() {
+                        Some(&ind) => {
+                            self.state.to_play = ind;
+                            return Ok(());
+                        },
+                        None => self.state.advance(RoundEvent::PiledOn)?,
+                    }
+                },
+                GameTurnType::EndRound(end) => {
+                    self.state.end_round(end);
+                    self.state.events.clear();
+                },
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Cards left in the hand in `seat`.
+    pub fn hand(&self, seat: usize) -> &[Card] {
+        &self.state.players[seat].hand
+    }
+
+    /// How the game went for the player in `seat` once it's over: 1 for a win, 0 for a loss and
+    /// 0.5 for a draw.
+    pub fn score(&self, seat: usize) -> Option<f64> {
+        if self.state.turn_type != GameTurnType::GameEnd { return None; }
+        Some(match self.state.clone().finish()[seat] {
+            Outcome::Won => 1.0,
+            Outcome::Draw => 0.5,
+            Outcome::Lost => 0.0,
+        })
+    }
 }
 
 impl GameState {
     pub fn new() -> Self {
         GameState {
             trump: Suit::Hearts,
+            trump_card: Card { rank: Rank::Six, suit: Suit::Hearts },
             players: Vec::new(),
             attackers: Vec::new(),
             attackers_passed: Vec::new(),
             draw_pile: Vec::with_capacity(36),
-            attack_cards: Vec::new(),
-            defense_cards: Vec::new(),
+            table: Table::new(),
             discarded_cards: Vec::new(),
             defender: 0usize,
             // attacker: 0usize,
             last_attacker: 0usize,
             to_play: 0usize,
             turn_type: GameTurnType::Attack,
+            rules: Rules::new(),
+            round_limit: None,
+            illegal_moves: IllegalMovePolicy::Abort,
+            observe_timeout: OBSERVE_TIMEOUT,
+            forfeited: None,
+            left: Vec::new(),
+            first_attack_reveal: None,
+            events: Vec::new(),
+            takeback: false,
+            undo: None,
+            stats: GameStats {
+                trump: Suit::Hearts,
+                rounds: 0,
+                first_attacker: 0,
+                initial_trumps: Vec::new(),
+                durak: None,
+                finish_order: Vec::new(),
+                player_ids: Vec::new(),
+                teams: None,
+                losing_team: None,
+                draw: false,
+                forfeited: None,
+                left: Vec::new(),
+            },
         }
     }
 
-    pub fn add_player(&mut self, id: u64) -> Result<()> {
-        if self.players.iter().any(|player| player.id == id) { bail!("Duplicate player id"); }
-        if self.players.len() >= 6 { bail!("Cannot add more than 6 players"); }
+    pub fn add_player(&mut self, id: u64, mut profile: Profile) -> Result<(),DurakError> {
+        self.check_new_player(id)?;
+        if profile.name.is_empty() { profile.name = format!("Player {}",id); }
+        // names are all the other players see, so no two can be the same
+        let name = profile.name.clone();
+        let mut n = 2;
+        while self.players.iter().any(|player| player.profile.name == profile.name) {
+            profile.name = format!("{} ({})",name,n);
+            n += 1;
+        }
         self.players.push(Player {
             id,
+            profile,
             hand: Vec::new(),
         });
         Ok(())
     }
 
-    pub fn init<R: Rng>(&mut self, rng: &mut R) -> Result<()> {
+    // whether a player with `id` can still join
+    fn check_new_player(&self, id: u64) -> Result<(),DurakError> {
+        if self.players.iter().any(|player| player.id == id) { return Err(DurakError::DuplicateId(id)); }
+        if self.players.len() >= 6 { return Err(DurakError::Config("Cannot add more than 6 players".to_owned())); }
+        Ok(())
+    }
+
+    pub fn init<R: Rng>(&mut self, rng: &mut R) -> Result<(),DurakError> {
         debug!("Initializing game");
         if self.players.len() < 2 {
-            bail!("Need at least two players to initialize game, only have ({})",self.players.len());
+            return Err(DurakError::Config(format!("Need at least two players to initialize game, only have ({})",self.players.len())));
         } else if self.players.len() > 6 {
-            bail!("Can't have more than 6 players");
+            return Err(DurakError::Config("Can't have more than 6 players".to_owned()));
+        }
+        if let Some(teams) = &self.rules.teams {
+            if teams.len() != self.players.len() {
+                return Err(DurakError::Config(format!("Have teams for {} seats but {} players",teams.len(),self.players.len())));
+            }
+            if teams.iter().all(|&team| team == teams[0]) {
+                return Err(DurakError::Config("Need at least two teams".to_owned()));
+            }
         }
 
         // shuffle deck
-        let mut in_order_cards = (0..36).map(|i| Card::try_from(i)).collect::<Result<Vec<Card>>>()?;
+        let mut in_order_cards = (0..36).map(Card::try_from).collect::<Result<Vec<Card>>>().map_err(|e| DurakError::Internal(e.to_string()))?;
         for _ in 0..36 {
             let index = rng.gen_range(0..in_order_cards.len());
             self.draw_pile.push(in_order_cards.swap_remove(index));
@@ -260,199 +904,1804 @@ impl GameState {
             }
         }
 
-        // determine trump suit
-        self.trump = self.draw_pile[0].suit;
+        // determine trump suit, with six players the whole deck is dealt so the last card dealt
+        // sets it instead
+        self.trump_card = match self.draw_pile.first() {
+            Some(&card) => card,
+            None => *self.players.last().and_then(|p| p.hand.last()).unwrap(),
+        };
+        self.trump = self.trump_card.suit;
         debug!("Trump suit is {}",self.trump);
 
         for player in &self.players { debug!("Player # {} has cards: {}",player.id,hand_fmt(&player.hand)); }
 
-        self.to_play = 0;
-        self.defender = 1;
+        self.first_attack_reveal = None;
+        self.to_play = match self.rules.first_attacker {
+            FirstAttackerRule::LowestTrump => {
+                let lowest = self.players
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(ind,player)| player.hand.iter().filter(|c| c.suit == self.trump).min_by_key(|c| c.rank as usize).map(|&c| (ind,c)))
+                    .min_by_key(|(_,c)| c.rank as usize);
+                self.first_attack_reveal = lowest.map(|(_,c)| c);
+                lowest.map(|(ind,_)| ind).unwrap_or(0)
+            },
+            FirstAttackerRule::SeatOrder => 0,
+            FirstAttackerRule::Random => rng.gen_range(0..self.players.len()),
+        };
+        self.defender = self.next_defender();
         self.turn_type = GameTurnType::Attack;
 
-        self.attackers_passed.clear();
-        self.attackers = (0..self.players.len()).filter(|&ind| ind != self.defender).collect();
+        self.seat_attackers();
+
+        self.stats = GameStats {
+            trump: self.trump,
+            rounds: 0,
+            first_attacker: self.to_play,
+            initial_trumps: self.players.iter().map(|p| p.hand.iter().filter(|c| c.suit == self.trump).count()).collect(),
+            durak: None,
+            finish_order: Vec::new(),
+            player_ids: self.players.iter().map(|p| p.id).collect(),
+            teams: self.rules.teams.clone(),
+            losing_team: None,
+            draw: false,
+            forfeited: None,
+            left: Vec::new(),
+        };
+
+        Ok(())
+    }
 
+    // removes a card from a player's hand, a card that isn't there means a move got past validation
+    fn take_from_hand(&mut self, player_ind: usize, card: &Card) -> Result<Card,DurakError> {
+        match remove_card(&mut self.players[player_ind].hand,card) {
+            Some(card) => Ok(card),
+            None => Err(DurakError::Internal(format!("Card {} is not in player {}'s hand",card,self.players[player_ind].id))),
+        }
+    }
+
+    // every card of the deck must be in exactly one hand, on the table, in the talon or discarded
+    fn check_cards(&self) -> Result<(),DurakError> {
+        let mut cards: Vec<usize> = self.players.iter()
+            .flat_map(|player| player.hand.iter())
+            .chain(self.table.cards())
+            .chain(self.draw_pile.iter())
+            .chain(self.discarded_cards.iter())
+            .map(|&card| usize::try_from(card).unwrap())
+            .collect();
+        cards.sort();
+        if cards != (0..36).collect::<Vec<usize>>() {
+            return Err(DurakError::Internal(format!("Cards have been lost or duplicated, {} cards in play",cards.len())));
+        }
+        Ok(())
+    }
+
+    // wraps an error returned by the player in seat `player_ind`
+    fn player_failed(&self, player_ind: usize) -> impl FnOnce(anyhow::Error) -> DurakError {
+        let id = self.players[player_ind].id;
+        move |source| DurakError::PlayerError { id, source }
+    }
+
+    // wraps a move by the player in seat `player_ind` that is against the rules
+    fn invalid_move(&self, player_ind: usize) -> impl FnOnce(ValidationError) -> DurakError {
+        let id = self.players[player_ind].id;
+        move |source| DurakError::InvalidMove { id, source }
+    }
+
+    // deals with an invalid move by the player in seat `player_ind` as the illegal move policy says
+    async fn on_invalid_move(&self, engines: &mut [Box<dyn DurakPlayer>], player_ind: usize, tries: &mut usize, error: ValidationError) -> Result<Retry,DurakError> {
+        debug!("Player # {} made an invalid move: {}",self.players[player_ind].id,error);
+        let retry = match self.illegal_moves {
+            IllegalMovePolicy::Abort => return Err(self.invalid_move(player_ind)(error)),
+            IllegalMovePolicy::Reprompt(max_tries) => {
+                *tries += 1;
+                if *tries > max_tries { Retry::Pass } else { Retry::Ask }
+            },
+            IllegalMovePolicy::AutoPass => Retry::Pass,
+            IllegalMovePolicy::Forfeit => Retry::Forfeit,
+        };
+        let msg = match retry {
+            Retry::Ask => format!("{}, try again",error),
+            Retry::Pass => format!("{}, passing instead",error),
+            Retry::Forfeit => format!("{}, you forfeit the game",error),
+        };
+        engines[player_ind].message(&msg).await.map_err(self.player_failed(player_ind))?;
+        Ok(retry)
+    }
+
+    // the player in seat `player_ind` leaves, ending the game as the durak or leaving their cards
+    // in the discard pile as the rules say
+    fn leave(&mut self, player_ind: usize) -> Result<(),DurakError> {
+        self.events.push(MoveEvent::Forfeited(self.players[player_ind].id));
+        match self.rules.leave {
+            LeaveRule::Durak => {
+                debug!("Ending game because player # {} forfeited",self.players[player_ind].id);
+                self.forfeited = Some(player_ind);
+                self.turn_type = GameTurnType::GameEnd;
+            },
+            LeaveRule::Discard => {
+                debug!("Player # {} left the game, discarding their cards",self.players[player_ind].id);
+                let mut hand = std::mem::take(&mut self.players[player_ind].hand);
+                self.discarded_cards.append(&mut hand);
+                self.left.push(player_ind);
+                if player_ind == self.defender {
+                    // nobody is left to beat or take the table, the next round works out who plays
+                    self.turn_type = GameTurnType::EndRound(RoundEnd::Defended);
+                } else if self.turn_type == GameTurnType::Attack {
+                    self.advance(RoundEvent::AttackerPassed)?;
+                }
+            },
+        }
         Ok(())
     }
 
+    // shows every player still in the game the last move, after `msg` if there is one. Players
+    // observe all at once and one that fails or runs out of time is only logged
+    async fn broadcast(&self, engines: &mut [Box<dyn DurakPlayer>], msg: Option<&str>, events: &[MoveEvent]) {
+        let player_info = get_player_info(self);
+        let observers = engines.iter_mut().enumerate()
+            // players who left may well have disconnected
+            .filter(|(i,_)| !self.has_left(*i))
+            .map(|(i,engine)| {
+                let to_play_state = gen_to_play_state_shared(self,i,&player_info);
+                async move {
+                    let observe = async {
+                        if let Some(msg) = msg { engine.message(msg).await?; }
+                        engine.observe_move(&to_play_state,events).await
+                    };
+                    match tokio::time::timeout(self.observe_timeout,observe).await {
+                        Ok(Ok(())) => {},
+                        Ok(Err(e)) => { warn!("Player # {} failed to observe the move: {}",self.players[i].id,e); },
+                        Err(_) => { warn!("Player # {} took too long to observe the move",self.players[i].id); },
+                    }
+                }
+            });
+        futures::future::join_all(observers).await;
+    }
+
+    // whether a player has left the game and no longer needs to hear about it
+    fn has_left(&self, player_ind: usize) -> bool {
+        self.left.contains(&player_ind) || self.forfeited == Some(player_ind)
+    }
+
     // refills a players hand from the talon up to 6 cards
     fn refill_from_talon(&mut self, player_ind: usize) {
-        while self.players[player_ind].hand.len() < 6 && self.draw_pile.len() > 0 {
+        if self.left.contains(&player_ind) { return; }
+        let mut drawn = 0;
+        while self.players[player_ind].hand.len() < 6 && !self.draw_pile.is_empty() {
             self.players[player_ind].hand.push(self.draw_pile.pop().unwrap());
+            drawn += 1;
         }
         sort_cards(&mut self.players[player_ind].hand,self.trump);
+        if drawn > 0 { self.events.push(MoveEvent::Drew(self.players[player_ind].id,drawn)); }
     }
 
+    // first attacker first, then clock-wise, defender last
     fn refill_players_hands(&mut self) {
         debug!("Refilling player's hands");
-        let attackers = self.attackers.clone();
-        for &ind in &attackers {
+        let (first,defender) = (self.attackers[0],self.defender);
+        let num_players = self.players.len();
+        for ind in (0..num_players).map(|i| (first + i) % num_players).filter(|&ind| ind != defender) {
             self.refill_from_talon(ind);
         }
         self.refill_from_talon(self.defender);
     }
 
+    fn team(&self, ind: usize) -> Option<usize> {
+        self.rules.teams.as_ref().and_then(|teams| teams.get(ind).copied())
+    }
 
-    async fn play_turn(&mut self, engines: &mut Vec<Box<dyn DurakPlayer>>) -> Result<()> {
-        debug!("Taking turn");
-        let to_play_state = gen_to_play_state(&self);
-        for player in &self.players {
-            debug!("Player # {} has cards: {}",player.id,player.hand.iter().map(|c| format!("{:>4}",format!("{}",c))).collect::<String>());
+    fn teammates(&self, a: usize, b: usize) -> bool {
+        self.team(a).is_some() && self.team(a) == self.team(b)
+    }
+
+    // the number of players, or teams when playing in teams, that still hold cards
+    fn sides_left(&self) -> usize {
+        let mut sides: Vec<(Option<usize>,usize)> = (0..self.players.len())
+            .filter(|&ind| !self.players[ind].hand.is_empty())
+            .map(|ind| match self.team(ind) {
+                Some(team) => (Some(team),0),
+                None => (None,ind),
+            })
+            .collect();
+        sides.sort();
+        sides.dedup();
+        sides.len()
+    }
+
+    // works out the durak, losing team or draw once the game is over, and each seat's outcome
+    fn finish(&mut self) -> Vec<Outcome> {
+        // a player who forfeits loses however many cards everyone else still holds
+        let holding: Vec<usize> = match self.forfeited {
+            Some(ind) => vec![ind],
+            // nobody loses to players who left, if they all did whoever is left holding cards wins
+            None => (0..self.players.len()).filter(|&ind| !self.players[ind].hand.is_empty() && !self.opponents_left(ind)).collect(),
+        };
+        let walkover = (0..self.players.len()).any(|ind| !self.players[ind].hand.is_empty() && self.opponents_left(ind));
+        // nobody is the durak if everyone got out, or the round limit cut the game short
+        self.stats.durak = match holding[..] {
+            [ind] => Some(ind),
+            _ => None,
+        };
+        self.stats.forfeited = self.forfeited;
+        self.stats.losing_team = match self.forfeited {
+            Some(ind) => self.team(ind),
+            None if walkover => None,
+            None => self.losing_team(),
+        };
+        self.stats.draw = self.stats.durak.is_none() && self.stats.losing_team.is_none() && !walkover;
+        self.stats.left = self.left.clone();
+        (0..self.players.len()).map(|ind| {
+            if self.left.contains(&ind) {
+                Outcome::Lost
+            } else if self.stats.draw {
+                Outcome::Draw
+            } else if holding.contains(&ind) || (self.stats.losing_team.is_some() && self.team(ind) == self.stats.losing_team) {
+                Outcome::Lost
+            } else {
+                Outcome::Won
+            }
+        }).collect()
+    }
+
+    // whether every opponent of the player in seat `ind` left the game
+    fn opponents_left(&self, ind: usize) -> bool {
+        (0..self.players.len())
+            .filter(|&other| other != ind && !self.teammates(ind,other))
+            .all(|other| self.left.contains(&other))
+    }
+
+    // the team holding the last cards, once every other team is out
+    fn losing_team(&self) -> Option<usize> {
+        if self.sides_left() != 1 { return None; }
+        (0..self.players.len()).find(|&ind| !self.players[ind].hand.is_empty()).and_then(|ind| self.team(ind))
+    }
+
+    // next player with cards after the first attacker (to_play), skipping their teammates
+    fn next_defender(&self) -> usize {
+        let num_players = self.players.len();
+        (1..num_players)
+            .map(|i| (self.to_play + i) % num_players)
+            .find(|&ind| !self.players[ind].hand.is_empty() && !self.teammates(ind,self.to_play))
+            .unwrap_or((self.to_play + 1) % num_players)
+    }
+
+    // works out who may attack this round from the first attacker (to_play) and the defender
+    fn seat_attackers(&mut self) {
+        let num_players = self.players.len();
+        self.attackers_passed.clear();
+        self.attackers = (0..num_players)
+            .map(|i| (self.to_play + i) % num_players)
+            .filter(|&ind| ind != self.defender && !self.players[ind].hand.is_empty() && !self.teammates(ind,self.defender))
+            .collect();
+        if self.rules.attackers == AttackerRule::Neighbours {
+            // the first attacker sits before the defender, so the other neighbour is the next
+            // player with cards after the defender
+            let after_defender = (1..num_players)
+                .map(|i| (self.defender + i) % num_players)
+                .find(|&ind| self.attackers.contains(&ind));
+            let first = self.attackers[0];
+            self.attackers.retain(|&ind| ind == first || Some(ind) == after_defender);
         }
-        debug!("Player # {} is the attacker",self.players[self.attackers[0]].id);
-        debug!("Player # {} is the defender",self.players[self.defender].id);
-        debug!("Player # {} is playing",self.players[self.to_play].id);
+    }
 
-        match &self.turn_type {
-            GameTurnType::Attack => {
-                debug!("Attack turn");
-                let attack = {
-                    if self.players[self.to_play].hand.len() == 0 { 
-                        debug!("Skipping turn because player has no cards left");
-                        Action::Pass
-                    } else if self.players[self.defender].hand.len() == 0 {
-                        debug!("Skipping turn because defender has no cards left");
-                        Action::Pass
-                    } else {
-                        debug!("Querying player for attack");
-                        let attack = engines[self.to_play].attack(&to_play_state).await?;
-                        if attack == Action::Pass { debug!("Player has selected to pass"); }
-                        attack
-                    }
-                };
-                match attack {
-                    Action::Play(attack_card) => {
-                        debug!("Player has selected {}",attack_card);
-                        to_play_state.validate_attack(&attack)?;
-                        transfer_card(&mut self.players[self.to_play].hand,&mut self.attack_cards,&attack_card);
-                        self.attackers_passed.clear();
-                        self.last_attacker = self.to_play;
-                        self.to_play = self.defender;
-                        self.turn_type = GameTurnType::Defense;
-                    },
-                    Action::Pass => {
-                        // bump to next attacker's turn, skipping those that have passed since last
-                        // attack move
-                        self.attackers_passed.push(self.to_play);
-                        match self.attackers.iter().filter(|ind| !self.attackers_passed.contains(ind) && self.players[**ind].hand.len() != 0).next() {
-                            Some(&ind) => self.to_play = ind,
-                            None => {
-                                debug!("Ending round because all attackers passed");
-                                self.to_play = self.defender;
-                                self.turn_type = GameTurnType::EndRound;
-                            }
-                        }
-                    },
-                }
-            },
-            GameTurnType::Defense => {
-                debug!("Defense turn");
-                match engines[self.to_play].defend(&to_play_state).await? {
-                    Action::Play(defense_card) => {
-                        debug!("Player has selected {}",defense_card);
-                        to_play_state.validate_defense(&Action::Play(defense_card))?;
-                        transfer_card(&mut self.players[self.to_play].hand,&mut self.defense_cards,&defense_card);
-                        if self.defense_cards.len() == 6 || self.players[self.to_play].hand.len() == 0 {
-                            debug!("Ending round because attack has been successfully defended");
-                            // defender has priority for next round
-                            self.to_play = self.defender;
-                            self.turn_type = GameTurnType::EndRound;
-                        } else {
-                            // last attacker has dibs on attacking next
-                            self.to_play = self.last_attacker;
-                            self.turn_type = GameTurnType::Attack;
-                        }
-                    },
-                    Action::Pass => {
-                        debug!("Player has selected to pass");
-                        self.turn_type = GameTurnType::PileOn;
-                    },
-                }
-            },
-            GameTurnType::PileOn => {
-                debug!("Pile on turn");
-                for ind_pile in 0..self.players.len() {
-                    if ind_pile == self.defender { continue; }
-                    self.to_play = ind_pile;
-                    let to_play_state = gen_to_play_state(&self);
-                    let pile_on_cards = engines[ind_pile].pile_on(&to_play_state).await?;
-                    to_play_state.validate_pile_on(&pile_on_cards)?;
-                    debug!("Player {} has piled on {}",self.players[ind_pile].id,hand_fmt(&pile_on_cards));
+    // whether the player in `seat` is asked to attack or defend this turn
+    fn prompts(&self, seat: usize) -> bool {
+        self.to_play == seat && match self.turn_type {
+            GameTurnType::Attack => self.may_attack(seat) && self.attack_room() > 0,
+            GameTurnType::Defense => true,
+            _ => false,
+        }
+    }
 
-                    for card in pile_on_cards {
-                        transfer_card(&mut self.players[ind_pile].hand,&mut self.attack_cards,&card);
-                    }
-                }
-                // defender is not the first attacker for next round
-                self.to_play = (self.defender + 1) % self.players.len();
-                self.turn_type = GameTurnType::EndRound;
+    // whether a player may attack right now
+    fn may_attack(&self, ind: usize) -> bool {
+        self.attackers.contains(&ind)
+            && !self.attackers_passed.contains(&ind)
+            && !self.players[ind].hand.is_empty()
+            && (self.rules.attackers != AttackerRule::FirstThenOthers || !self.table.is_empty() || ind == self.attackers[0])
+    }
+
+    // how many more cards may be played against the defender this round
+    fn attack_room(&self) -> usize {
+        let defender_cards = self.players[self.defender].hand.len().saturating_sub(self.table.open_attacks().count());
+        (6usize.saturating_sub(self.table.len())).min(defender_cards)
+    }
+
+    // the round state machine, works out whose turn it is and what kind of turn after `event`
+    fn advance(&mut self, event: RoundEvent) -> Result<(),DurakError> {
+        self.turn_type = match (&self.turn_type,event) {
+            (GameTurnType::Attack,RoundEvent::Attacked) => {
+                self.attackers_passed.clear();
+                self.last_attacker = self.to_play;
+                self.to_play = self.defender;
+                GameTurnType::Defense
             },
-            GameTurnType::EndRound => {
-                if self.to_play == self.defender {
-                    // successful defense
-                    self.discarded_cards.append(&mut self.attack_cards);
-                    self.discarded_cards.append(&mut self.defense_cards);
+            (GameTurnType::Attack,RoundEvent::AttackerPassed) => {
+                self.attackers_passed.push(self.to_play);
+                self.next_attack()
+            },
+            (GameTurnType::Defense,RoundEvent::Beat) => {
+                if self.table.open_attacks().next().is_some() {
+                    debug!("Defender still has attacks to beat");
+                    GameTurnType::Defense
                 } else {
-                    // unsuccessful defense
-                    self.players[self.defender].hand.append(&mut self.attack_cards);
-                    self.players[self.defender].hand.append(&mut self.defense_cards);
-                }
-                self.refill_players_hands();
-                if self.players.iter().map(|player| { 
-                    match player.hand.len() {
-                        0 => 0,
-                        _ => 1,
-                    }
-                }).fold(0, |a,b| a + b) <= 1 {
-                    self.turn_type = GameTurnType::GameEnd;
-                    return Ok(());
-                }
-
-                // select players with cards left
-                self.attackers = (0..self.players.len()).filter(|ind| self.players[*ind].hand.len() != 0).collect();
-                // find next in order
-                while !self.attackers.contains(&self.to_play) {
-                    self.to_play = (self.to_play + 1) % self.players.len();
+                    // last attacker has dibs on attacking next
+                    self.to_play = self.last_attacker;
+                    self.next_attack()
                 }
-                // rotate so they're first in line
-                let offset = self.attackers.iter().enumerate().filter(|(_,ind)| **ind == self.to_play).next().unwrap().0;
-                self.attackers.rotate_left(offset);
-                // second in line is defender
-                self.defender = self.attackers[1];
-                self.attackers.remove(1);
-                self.turn_type = GameTurnType::Attack;
             },
-            GameTurnType::GameEnd => {},
-        }
+            (GameTurnType::Defense,RoundEvent::DefenderTook) => GameTurnType::PileOn,
+            (GameTurnType::PileOn,RoundEvent::PiledOn) => GameTurnType::EndRound(RoundEnd::Taken),
+            (turn_type,event) => return Err(DurakError::Internal(format!("Can't move round on from {:?} after {:?}",turn_type,event))),
+        };
         Ok(())
     }
-}
 
-fn gen_to_play_state(state: &GameState) -> ToPlayState {
-    gen_to_play_state_w_hand(state,state.to_play)
-}
+    // the next attack turn, or the end of the round if nobody may or can attack
+    fn next_attack(&mut self) -> GameTurnType {
+        if self.attack_room() == 0 {
+            debug!("Ending round because no more cards can be played against the defender");
+            return GameTurnType::EndRound(RoundEnd::Defended);
+        }
+        if self.may_attack(self.to_play) {
+            return GameTurnType::Attack;
+        }
+        // skip attackers that have passed since the last attack or have run out of cards
+        match self.attackers.iter().find(|&&ind| self.may_attack(ind)) {
+            Some(&ind) => {
+                self.to_play = ind;
+                GameTurnType::Attack
+            },
+            None => {
+                debug!("Ending round because all attackers passed");
+                GameTurnType::EndRound(RoundEnd::Defended)
+            },
+        }
+    }
 
-fn gen_to_play_state_w_hand(state: &GameState, hand_ind: usize) -> ToPlayState {
+    // clears the table at the end of a round, refills hands and seats the next round
+    fn end_round(&mut self, end: RoundEnd) {
+        match end {
+            RoundEnd::Defended => {
+                self.events.push(MoveEvent::RoundDiscarded);
+                self.discarded_cards.append(&mut self.table.take_all());
+                // defender has priority for next round
+                self.to_play = self.defender;
+            },
+            RoundEnd::Taken => {
+                // beaten attacks are taken along with their defenses
+                let mut taken = self.table.take_all();
+                self.events.push(MoveEvent::TookCards(self.players[self.defender].id,taken.len()));
+                self.players[self.defender].hand.append(&mut taken);
+                // defender is not the first attacker for next round
+                self.to_play = (self.defender + 1) % self.players.len();
+            },
+        }
+        self.refill_players_hands();
+        self.stats.rounds += 1;
+        // anyone who has run out of cards now is safely out of the game
+        for ind in 0..self.players.len() {
+            if self.players[ind].hand.is_empty() && !self.stats.finish_order.contains(&ind) && !self.left.contains(&ind) {
+                self.stats.finish_order.push(ind);
+                self.events.push(MoveEvent::PlayerOut(self.players[ind].id));
+            }
+        }
+        if self.sides_left() <= 1 {
+            self.turn_type = GameTurnType::GameEnd;
+            return;
+        }
+        if self.round_limit.is_some_and(|limit| self.stats.rounds >= limit) {
+            debug!("Ending game because the round limit was reached");
+            self.turn_type = GameTurnType::GameEnd;
+            return;
+        }
+
+        // find next in order with cards left
+        while self.players[self.to_play].hand.is_empty() {
+            self.to_play = (self.to_play + 1) % self.players.len();
+        }
+        self.defender = self.next_defender();
+        self.seat_attackers();
+        self.turn_type = GameTurnType::Attack;
+    }
+
+
+    // the player who may swap the six of trumps for the face up trump card, if any
+    fn trump_exchange_holder(&self) -> Option<usize> {
+        if !self.rules.trump_exchange { return None; }
+        if self.trump_card.rank == Rank::Six || self.draw_pile.first() != Some(&self.trump_card) { return None; }
+        let six = Card { rank: Rank::Six, suit: self.trump };
+        self.players.iter().position(|player| player.hand.contains(&six))
+    }
+
+    async fn offer_trump_exchange(&mut self, engines: &mut [Box<dyn DurakPlayer>]) -> Result<(),DurakError> {
+        let Some(holder) = self.trump_exchange_holder() else { return Ok(()); };
+        if !engines[holder].exchange_trump(&gen_to_play_state_w_hand(self,holder)).await.map_err(self.player_failed(holder))? { return Ok(()); }
+
+        let six = Card { rank: Rank::Six, suit: self.trump };
+        let face_up = self.trump_card;
+        self.draw_pile[0] = self.take_from_hand(holder,&six)?;
+        self.trump_card = six;
+        self.players[holder].hand.push(face_up);
+        sort_cards(&mut self.players[holder].hand,self.trump);
+        let event = MoveEvent::ExchangedTrump(self.players[holder].id,six,face_up);
+        let msg = event.to_string();
+        debug!("{}",msg);
+        self.broadcast(engines,Some(&msg),&[event]).await;
+        Ok(())
+    }
+
+    // plays an attack that has passed validation
+    fn apply_attack(&mut self, attack: Action) -> Result<(),DurakError> {
+        match attack {
+            Action::Play(_) | Action::PlayMany(_) => {
+                let attack_cards = match attack {
+                    Action::PlayMany(cards) => cards,
+                    Action::Play(card) => vec![card],
+                    _ => unreachable!(),
+                };
+                debug!("Player has selected {}",hand_fmt(&attack_cards));
+                for attack_card in attack_cards {
+                    let card = self.take_from_hand(self.to_play,&attack_card)?;
+                    self.table.add_attack(card);
+                    self.events.push(MoveEvent::Attacked(self.players[self.to_play].id,card));
+                }
+                self.advance(RoundEvent::Attacked)?;
+            },
+            Action::Forfeit => {
+                self.leave(self.to_play)?;
+            },
+            Action::Undo => {
+                self.undo = Some(self.to_play);
+            },
+            // a defense never validates as an attack
+            Action::Defend { .. } | Action::Pass => {
+                debug!("Player has selected to pass");
+                self.events.push(MoveEvent::Passed(self.players[self.to_play].id));
+                self.advance(RoundEvent::AttackerPassed)?;
+            },
+        }
+        Ok(())
+    }
+
+    // plays a defense that has passed validation
+    fn apply_defense(&mut self, defense: Action) -> Result<(),DurakError> {
+        match defense {
+            Action::Defend { card: defense_card, against } => {
+                debug!("Player has selected {} against {}",defense_card,against);
+                let card = self.take_from_hand(self.to_play,&defense_card)?;
+                self.table.add_defense(card,against);
+                self.events.push(MoveEvent::Defended(self.players[self.to_play].id,card));
+                self.advance(RoundEvent::Beat)?;
+            },
+            Action::Forfeit => {
+                self.leave(self.to_play)?;
+            },
+            Action::Undo => {
+                self.undo = Some(self.to_play);
+            },
+            // attacks never validate as a defense
+            Action::Play(_) | Action::PlayMany(_) | Action::Pass => {
+                debug!("Player has selected to pass");
+                self.events.push(MoveEvent::Passed(self.players[self.to_play].id));
+                self.advance(RoundEvent::DefenderTook)?;
+            },
+        }
+        Ok(())
+    }
+
+    // adds cards that have passed validation to the table for the defender to take
+    fn apply_pile_on(&mut self, ind_pile: usize, pile_on_cards: Vec<Card>) -> Result<(),DurakError> {
+        debug!("Player {} has piled on {}",self.players[ind_pile].id,hand_fmt(&pile_on_cards));
+        if !pile_on_cards.is_empty() {
+            self.events.push(MoveEvent::PiledOn(self.players[ind_pile].id,pile_on_cards.clone()));
+        }
+
+        for card in pile_on_cards {
+            let card = self.take_from_hand(ind_pile,&card)?;
+            self.table.add_attack(card);
+        }
+        Ok(())
+    }
+
+    async fn play_turn(&mut self, engines: &mut [Box<dyn DurakPlayer>]) -> Result<(),DurakError> {
+        debug!("Taking turn");
+        if self.turn_type == GameTurnType::Attack && self.table.is_empty() && self.attackers_passed.is_empty() {
+            self.offer_trump_exchange(engines).await?;
+        }
+        let to_play_state = gen_to_play_state(self);
+        for player in &self.players {
+            debug!("Player # {} has cards: {}",player.id,player.hand.iter().map(|c| format!("{:>4}",format!("{}",c))).collect::<String>());
+        }
+        debug!("Player # {} is the attacker",self.players[self.attackers[0]].id);
+        debug!("Player # {} is the defender",self.players[self.defender].id);
+        debug!("Player # {} is playing",self.players[self.to_play].id);
+
+        match &self.turn_type {
+            GameTurnType::Attack => {
+                debug!("Attack turn");
+                let mut tries = 0;
+                let attack = loop {
+                    if !self.may_attack(self.to_play) || self.attack_room() == 0 {
+                        debug!("Skipping turn because player can't attack");
+                        return self.advance(RoundEvent::AttackerPassed);
+                    }
+                    debug!("Querying player for attack");
+                    let attack = engines[self.to_play].attack(&to_play_state).await.map_err(self.player_failed(self.to_play))?;
+                    let valid = match attack {
+                        Action::Pass => Ok(()),
+                        _ => to_play_state.validate_attack(&attack),
+                    };
+                    match valid {
+                        Ok(()) => break attack,
+                        Err(e) => match self.on_invalid_move(engines,self.to_play,&mut tries,e).await? {
+                            Retry::Ask => {},
+                            Retry::Pass => break Action::Pass,
+                            Retry::Forfeit => break Action::Forfeit,
+                        },
+                    }
+                };
+                self.apply_attack(attack)?;
+            },
+            GameTurnType::Defense => {
+                debug!("Defense turn");
+                let mut tries = 0;
+                let defense = loop {
+                    let defense = engines[self.to_play].defend(&to_play_state).await.map_err(self.player_failed(self.to_play))?;
+                    let valid = match defense {
+                        Action::Pass => Ok(()),
+                        _ => to_play_state.validate_defense(&defense),
+                    };
+                    match valid {
+                        Ok(()) => break defense,
+                        Err(e) => match self.on_invalid_move(engines,self.to_play,&mut tries,e).await? {
+                            Retry::Ask => {},
+                            Retry::Pass => break Action::Pass,
+                            Retry::Forfeit => break Action::Forfeit,
+                        },
+                    }
+                };
+                self.apply_defense(defense)?;
+            },
+            GameTurnType::PileOn => {
+                debug!("Pile on turn");
+                for ind_pile in self.attackers.clone() {
+                    // an attacker may have left earlier in the round
+                    if self.has_left(ind_pile) { continue; }
+                    self.to_play = ind_pile;
+                    let to_play_state = gen_to_play_state(self);
+                    let mut tries = 0;
+                    let pile_on_cards = loop {
+                        let pile_on_cards = engines[ind_pile].pile_on(&to_play_state).await.map_err(self.player_failed(ind_pile))?;
+                        match to_play_state.validate_pile_on(&pile_on_cards) {
+                            Ok(()) => break pile_on_cards,
+                            Err(e) => match self.on_invalid_move(engines,ind_pile,&mut tries,e).await? {
+                                Retry::Ask => {},
+                                Retry::Pass => break Vec::new(),
+                                Retry::Forfeit => {
+                                    self.leave(ind_pile)?;
+                                    if self.turn_type == GameTurnType::GameEnd { return Ok(()); }
+                                    break Vec::new();
+                                },
+                            },
+                        }
+                    };
+                    self.apply_pile_on(ind_pile,pile_on_cards)?;
+                }
+                self.advance(RoundEvent::PiledOn)?;
+            },
+            &GameTurnType::EndRound(end) => {
+                self.end_round(end);
+            },
+            GameTurnType::GameEnd => {},
+        }
+        Ok(())
+    }
+}
+
+// puts `items[order[i]]` at `i`
+fn reorder<T>(items: Vec<T>, order: &[usize]) -> Vec<T> {
+    let mut items: Vec<Option<T>> = items.into_iter().map(Some).collect();
+    order.iter().map(|&ind| items[ind].take().unwrap()).collect()
+}
+
+fn gen_to_play_state(state: &GameState) -> ToPlayState<'_> {
+    gen_to_play_state_w_hand(state,state.to_play)
+}
+
+fn gen_to_play_state_w_hand(state: &GameState, hand_ind: usize) -> ToPlayState<'_> {
     ToPlayState {
-        attack_cards: Cow::Borrowed(&state.attack_cards),
-        defense_cards: Cow::Borrowed(&state.defense_cards),
+        table: Cow::Borrowed(&state.table),
         hand: Cow::Borrowed(&state.players[hand_ind].hand),
         trump: state.trump,
-        player_info: get_player_info(state),
+        trump_card: state.trump_card,
+        draw_pile_len: state.draw_pile.len(),
+        discard_len: state.discarded_cards.len(),
+        player_info: Cow::Owned(get_player_info(state)),
         last_attacker: state.last_attacker,
         defender: state.defender,
         to_play: state.to_play,
+        takeback: state.takeback,
+    }
+}
+
+// like gen_to_play_state_w_hand, but borrowing player info worked out once for every player
+fn gen_to_play_state_shared<'a>(state: &'a GameState, hand_ind: usize, player_info: &'a Vec<PlayerInfo>) -> ToPlayState<'a> {
+    ToPlayState {
+        table: Cow::Borrowed(&state.table),
+        hand: Cow::Borrowed(&state.players[hand_ind].hand),
+        trump: state.trump,
+        trump_card: state.trump_card,
+        draw_pile_len: state.draw_pile.len(),
+        discard_len: state.discarded_cards.len(),
+        player_info: Cow::Borrowed(player_info),
+        last_attacker: state.last_attacker,
+        defender: state.defender,
+        to_play: state.to_play,
+        takeback: state.takeback,
     }
 }
 
 fn get_player_info(state: &GameState) -> Vec<PlayerInfo> {
         state.players
             .iter()
-            .map(|player| { 
+            .enumerate()
+            .map(|(ind,player)| { 
                 PlayerInfo {
                     id: player.id,
-                    hand_len: player.hand.len()
+                    profile: player.profile.clone(),
+                    hand_len: player.hand.len(),
+                    team: state.team(ind),
                 }
             }).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use rand::{Rng,SeedableRng};
+    use rand::rngs::StdRng;
+
+    // what a test player does when it's asked to play
+    #[derive(Clone,Copy,PartialEq,Default)]
+    enum Plays {
+        #[default]
+        Pass,
+        Forfeit,
+        // fails every turn, like a client that has disconnected
+        Fail,
+    }
+
+    // the hand, number of table cards and whether takeback was allowed each time a player was asked
+    type Seen = Vec<(Vec<Card>,usize,bool)>;
+
+    // everything a test player was told
+    #[derive(Default)]
+    struct Record {
+        offered: Vec<usize>,
+        seated: Vec<(u64,usize)>,
+        messages: Vec<String>,
+        events: Vec<MoveEvent>,
+        observed: usize,
+        outcomes: Vec<&'static str>,
+        seen: Seen,
+    }
+
+    type Log = std::sync::Arc<std::sync::Mutex<Record>>;
+
+    // plays the same move every turn, always takes a trump exchange, leaves draw() to the default
+    // and records what it is told in its log
+    #[derive(Default)]
+    struct TestPlayer {
+        plays: Plays,
+        // the ID and seat it asks for
+        id: Option<u64>,
+        seat: Option<usize>,
+        // attacks with a card it doesn't have this many times first
+        invalid: usize,
+        // makes a move, then takes it back
+        takeback: bool,
+        remote: bool,
+        // never gets round to observing a move
+        slow: bool,
+        log: Log,
+    }
+
+    impl TestPlayer {
+        fn new(plays: Plays) -> Self {
+            TestPlayer { plays, ..Default::default() }
+        }
+
+        fn with_log(mut self, log: &Log) -> Self {
+            self.log = log.clone();
+            self
+        }
+
+        fn with_id(mut self, id: u64) -> Self {
+            self.id = Some(id);
+            self
+        }
+
+        fn with_seat(mut self, seat: Option<usize>) -> Self {
+            self.seat = seat;
+            self
+        }
+
+        fn with_invalid(mut self, invalid: usize) -> Self {
+            self.invalid = invalid;
+            self
+        }
+
+        fn with_takeback(mut self, remote: bool) -> Self {
+            self.takeback = true;
+            self.remote = remote;
+            self
+        }
+
+        fn slow(mut self) -> Self {
+            self.slow = true;
+            self
+        }
+
+        fn boxed(self) -> Box<dyn DurakPlayer> {
+            Box::new(self)
+        }
+
+        fn next_move(&mut self, state: &ToPlayState, first: Action) -> Result<Action> {
+            if self.plays == Plays::Fail { return Err(anyhow::anyhow!("Connection reset")); }
+            if self.invalid > 0 {
+                self.invalid -= 1;
+                let missing = (0..36).map(|i| Card::try_from(i).unwrap()).find(|c| !state.hand.contains(c)).unwrap();
+                return Ok(Action::Play(missing));
+            }
+            if self.takeback {
+                let mut log = self.log.lock().unwrap();
+                log.seen.push((state.hand.to_vec(),state.table.len(),state.takeback));
+                match log.seen.len() {
+                    1 => return Ok(first),
+                    2 => return Ok(Action::Undo),
+                    _ => {},
+                }
+            }
+            Ok(match self.plays {
+                Plays::Forfeit => Action::Forfeit,
+                _ => Action::Pass,
+            })
+        }
+
+        fn fails(&self) -> Result<()> {
+            match self.plays {
+                Plays::Fail => Err(anyhow::anyhow!("Connection reset")),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl DurakPlayer for TestPlayer {
+        async fn attack(&mut self, state: &ToPlayState) -> Result<Action> {
+            let first = state.hand.first().map_or(Action::Pass,|&card| Action::Play(card));
+            self.next_move(state,first)
+        }
+        async fn defend(&mut self, state: &ToPlayState) -> Result<Action> { self.next_move(state,Action::Pass) }
+        async fn pile_on(&mut self, _state: &ToPlayState) -> Result<Vec<Card>> {
+            self.fails()?;
+            Ok(Vec::new())
+        }
+        async fn observe_move(&mut self, _state: &ToPlayState, events: &[MoveEvent]) -> Result<()> {
+            self.fails()?;
+            if self.slow { tokio::time::sleep(Duration::from_secs(3600)).await; }
+            let mut log = self.log.lock().unwrap();
+            log.events.extend_from_slice(events);
+            log.observed += 1;
+            Ok(())
+        }
+        async fn exchange_trump(&mut self, _state: &ToPlayState) -> Result<bool> { Ok(true) }
+        async fn get_id(&mut self, player_info: &[PlayerInfo]) -> Result<u64> {
+            Ok(self.id.unwrap_or_else(|| player_info.iter().map(|info| info.id + 1).max().unwrap_or(0)))
+        }
+        fn is_remote(&self) -> bool { self.remote }
+        async fn choose_seat(&mut self, _player_info: &[PlayerInfo], seats: usize) -> Result<Option<usize>> {
+            self.log.lock().unwrap().offered.push(seats);
+            Ok(self.seat)
+        }
+        async fn seated(&mut self, id: u64, seat: usize, _rules: &Rules) -> Result<()> {
+            self.log.lock().unwrap().seated.push((id,seat));
+            Ok(())
+        }
+        async fn won(&mut self) -> Result<Ready> { self.log.lock().unwrap().outcomes.push("won"); Ok(Ready::Yes) }
+        async fn lost(&mut self) -> Result<Ready> { self.log.lock().unwrap().outcomes.push("lost"); Ok(Ready::Yes) }
+        async fn message(&mut self, msg: &str) -> Result<()> {
+            self.fails()?;
+            self.log.lock().unwrap().messages.push(msg.to_owned());
+            Ok(())
+        }
+    }
+
+    fn game_state(num_players: usize, attackers: AttackerRule) -> GameState {
+        let mut state = GameState::new();
+        state.rules = Rules::new().with_attackers(attackers).with_first_attacker(FirstAttackerRule::SeatOrder);
+        for id in 0..num_players {
+            state.add_player(id as u64,Profile::default()).unwrap();
+        }
+        state.init(&mut StdRng::seed_from_u64(0)).unwrap();
+        state
+    }
+
+    fn engines(num_players: usize) -> Vec<Box<dyn DurakPlayer>> {
+        (0..num_players).map(|_| TestPlayer::default().boxed()).collect()
+    }
+
+    // ends a round with a successful defense, making the defender the next first attacker
+    async fn next_round(state: &mut GameState, engines: &mut [Box<dyn DurakPlayer>]) {
+        state.turn_type = GameTurnType::EndRound(RoundEnd::Defended);
+        state.play_turn(engines).await.unwrap();
+    }
+
+    #[test]
+    fn all_players_attack() {
+        let state = game_state(4,AttackerRule::All);
+        assert_eq!(state.defender,1);
+        assert_eq!(state.attackers,vec![0,2,3]);
+    }
+
+    #[test]
+    fn neighbours_attack() {
+        let state = game_state(4,AttackerRule::Neighbours);
+        assert_eq!(state.attackers,vec![0,2]);
+        let state = game_state(2,AttackerRule::Neighbours);
+        assert_eq!(state.attackers,vec![0]);
+    }
+
+    #[test]
+    fn first_then_others_attack() {
+        let state = game_state(4,AttackerRule::FirstThenOthers);
+        assert_eq!(state.attackers,vec![0,2,3]);
+    }
+
+    #[tokio::test]
+    async fn neighbours_rotate_with_defender() {
+        let mut state = game_state(5,AttackerRule::Neighbours);
+        let mut engines = engines(5);
+        next_round(&mut state,&mut engines).await;
+        assert_eq!((state.to_play,state.defender),(1,2));
+        assert_eq!(state.attackers,vec![1,3]);
+        next_round(&mut state,&mut engines).await;
+        assert_eq!(state.attackers,vec![2,4]);
+        next_round(&mut state,&mut engines).await;
+        assert_eq!(state.attackers,vec![3,0]);
+    }
+
+    #[tokio::test]
+    async fn neighbours_skip_players_without_cards() {
+        let mut state = game_state(5,AttackerRule::Neighbours);
+        state.players[2].hand.clear();
+        state.draw_pile.clear();
+        let mut engines = engines(5);
+        next_round(&mut state,&mut engines).await;
+        assert_eq!((state.to_play,state.defender),(1,3));
+        assert_eq!(state.attackers,vec![1,4]);
+    }
+
+    #[tokio::test]
+    async fn all_players_may_lead_after_a_pass() {
+        let mut state = game_state(4,AttackerRule::All);
+        let mut engines = engines(4);
+        state.play_turn(&mut engines).await.unwrap();
+        assert!(state.turn_type == GameTurnType::Attack);
+        assert_eq!(state.to_play,2);
+        state.play_turn(&mut engines).await.unwrap();
+        assert_eq!(state.to_play,3);
+        state.play_turn(&mut engines).await.unwrap();
+        assert!(state.turn_type == GameTurnType::EndRound(RoundEnd::Defended));
+    }
+
+    #[tokio::test]
+    async fn neighbours_never_ask_other_players() {
+        let mut state = game_state(4,AttackerRule::Neighbours);
+        let mut engines = engines(4);
+        state.play_turn(&mut engines).await.unwrap();
+        assert_eq!(state.to_play,2);
+        state.play_turn(&mut engines).await.unwrap();
+        assert!(state.turn_type == GameTurnType::EndRound(RoundEnd::Defended));
+    }
+
+    #[tokio::test]
+    async fn only_first_attacker_may_lead() {
+        let mut state = game_state(4,AttackerRule::FirstThenOthers);
+        let mut engines = engines(4);
+        state.play_turn(&mut engines).await.unwrap();
+        assert!(state.turn_type == GameTurnType::EndRound(RoundEnd::Defended));
+    }
+
+    #[tokio::test]
+    async fn others_throw_in_after_first_attack() {
+        let mut state = game_state(4,AttackerRule::FirstThenOthers);
+        let mut engines = engines(4);
+        let card = state.players[0].hand.pop().unwrap();
+        state.table.add_attack(card);
+        state.play_turn(&mut engines).await.unwrap();
+        assert!(state.turn_type == GameTurnType::Attack);
+        assert_eq!(state.to_play,2);
+    }
+
+    // deals the six of trumps to a player, swapping it with one of their cards
+    fn give_trump_six(state: &mut GameState, ind: usize) -> Card {
+        let six = Card { rank: Rank::Six, suit: state.trump };
+        let swap = state.players[ind].hand[0];
+        for cards in state.players.iter_mut().map(|p| &mut p.hand).chain(std::iter::once(&mut state.draw_pile)) {
+            for card in cards.iter_mut() {
+                if *card == six { *card = swap; } else if *card == swap { *card = six; }
+            }
+        }
+        six
+    }
+
+    #[tokio::test]
+    async fn trump_six_exchanged_for_face_up_card() {
+        let mut state = game_state(2,AttackerRule::All);
+        state.rules.trump_exchange = true;
+        assert_ne!(state.trump_card.rank,Rank::Six);
+        let face_up = state.trump_card;
+        let six = give_trump_six(&mut state,1);
+        let log = Log::default();
+        let mut engines = vec![TestPlayer::default().with_log(&log).boxed(),TestPlayer::default().boxed()];
+        state.play_turn(&mut engines).await.unwrap();
+        // everyone is told, both with a message and as an event
+        let event = MoveEvent::ExchangedTrump(1,six,face_up);
+        assert_eq!(log.lock().unwrap().messages,vec![event.to_string()]);
+        assert_eq!(log.lock().unwrap().events,vec![event]);
+        assert!(state.players[1].hand.contains(&face_up));
+        assert!(!state.players[1].hand.contains(&six));
+        assert_eq!(state.draw_pile[0],six);
+        assert_eq!(state.trump_card,six);
+        assert_eq!(state.trump_exchange_holder(),None);
+    }
+
+    #[tokio::test]
+    async fn trump_exchange_needs_rule() {
+        let mut state = game_state(2,AttackerRule::All);
+        let face_up = state.trump_card;
+        let six = give_trump_six(&mut state,1);
+        state.play_turn(&mut engines(2)).await.unwrap();
+        assert!(state.players[1].hand.contains(&six));
+        assert_eq!(state.trump_card,face_up);
+    }
+
+    #[test]
+    fn lowest_trump_attacks_first() {
+        for seed in 0..20 {
+            let mut state = GameState::new();
+            for id in 0..4 { state.add_player(id,Profile::default()).unwrap(); }
+            state.init(&mut StdRng::seed_from_u64(seed)).unwrap();
+            let trump = state.trump;
+            let lowest = state.players.iter().flat_map(|p| p.hand.iter()).filter(|c| c.suit == trump).min_by_key(|c| c.rank as usize).copied();
+            match lowest {
+                Some(card) => {
+                    assert!(state.players[state.to_play].hand.contains(&card));
+                    assert_eq!(state.first_attack_reveal,Some(card));
+                },
+                None => assert_eq!(state.to_play,0),
+            }
+            assert_eq!(state.defender,(state.to_play + 1) % 4);
+            assert_eq!(state.attackers[0],state.to_play);
+            assert!(!state.attackers.contains(&state.defender));
+        }
+    }
+
+    #[tokio::test]
+    async fn lowest_trump_is_shown_to_everyone() {
+        let log = Log::default();
+        let mut game = DurakGame::new();
+        for _ in 0..4 { game.add_player(TestPlayer::new(Plays::Forfeit).with_log(&log).boxed()).await.unwrap(); }
+        game.init(&mut StdRng::seed_from_u64(0)).unwrap();
+        let card = game.state.first_attack_reveal.unwrap();
+        let id = game.state.players[game.state.to_play].id;
+        game.run_game().await.unwrap();
+        let log = log.lock().unwrap();
+        assert_eq!(log.events[..4],vec![MoveEvent::ShowedLowestTrump(id,card);4]);
+        assert!(log.messages.is_empty());
+    }
+
+    #[test]
+    fn random_first_attacker() {
+        let mut first = Vec::new();
+        for seed in 0..20 {
+            let mut state = GameState::new();
+            state.rules = Rules::new().with_first_attacker(FirstAttackerRule::Random);
+            for id in 0..4 { state.add_player(id,Profile::default()).unwrap(); }
+            state.init(&mut StdRng::seed_from_u64(seed)).unwrap();
+            assert_eq!(state.first_attack_reveal,None);
+            assert_eq!(state.defender,(state.to_play + 1) % 4);
+            first.push(state.to_play);
+        }
+        assert!(first.iter().any(|&ind| ind != first[0]));
+    }
+
+    fn team_state(teams: Vec<usize>) -> GameState {
+        let mut state = GameState::new();
+        state.rules = Rules::new().with_first_attacker(FirstAttackerRule::SeatOrder).with_teams(teams.clone());
+        for id in 0..teams.len() { state.add_player(id as u64,Profile::default()).unwrap(); }
+        state.init(&mut StdRng::seed_from_u64(0)).unwrap();
+        state
+    }
+
+    #[test]
+    fn partners_never_attack_each_other() {
+        let state = team_state(vec![0,1,0,1]);
+        assert_eq!((state.to_play,state.defender),(0,1));
+        assert_eq!(state.attackers,vec![0,2]);
+        let state = team_state(vec![0,1,2,0,1,2]);
+        assert_eq!(state.attackers,vec![0,2,3,5]);
+    }
+
+    #[test]
+    fn defender_is_next_opponent() {
+        let state = team_state(vec![0,0,1,1]);
+        assert_eq!((state.to_play,state.defender),(0,2));
+        assert_eq!(state.attackers,vec![0,1]);
+    }
+
+    #[test]
+    fn player_info_has_teams() {
+        let state = team_state(vec![0,1,0,1]);
+        let teams: Vec<Option<usize>> = get_player_info(&state).iter().map(|info| info.team).collect();
+        assert_eq!(teams,vec![Some(0),Some(1),Some(0),Some(1)]);
+    }
+
+    #[test]
+    fn teams_must_match_players() {
+        let mut state = GameState::new();
+        state.rules = Rules::new().with_teams(vec![0,1,0]);
+        for id in 0..4 { state.add_player(id,Profile::default()).unwrap(); }
+        assert!(matches!(state.init(&mut StdRng::seed_from_u64(0)),Err(DurakError::Config(_))));
+    }
+
+    #[test]
+    fn teams_need_two_sides() {
+        let mut state = GameState::new();
+        state.rules = Rules::new().with_teams(vec![0,0]);
+        for id in 0..2 { state.add_player(id,Profile::default()).unwrap(); }
+        assert!(matches!(state.init(&mut StdRng::seed_from_u64(0)),Err(DurakError::Config(_))));
+    }
+
+    #[test]
+    fn setup_errors() {
+        let mut state = GameState::new();
+        state.add_player(7,Profile::default()).unwrap();
+        assert!(matches!(state.add_player(7,Profile::default()),Err(DurakError::DuplicateId(7))));
+        assert!(matches!(state.init(&mut StdRng::seed_from_u64(0)),Err(DurakError::Config(_))));
+        for id in 0..5 { state.add_player(id,Profile::default()).unwrap(); }
+        assert!(matches!(state.add_player(5,Profile::default()),Err(DurakError::Config(_))));
+    }
+
+    #[test]
+    fn names_are_unique() {
+        let mut state = GameState::new();
+        state.add_player(3,Profile::default()).unwrap();
+        state.add_player(4,Profile::bot("Dummy")).unwrap();
+        state.add_player(5,Profile::bot("Dummy")).unwrap();
+        state.add_player(6,Profile::new("Player 3")).unwrap();
+        let names: Vec<String> = get_player_info(&state).into_iter().map(|info| info.profile.name).collect();
+        assert_eq!(names,vec!["Player 3","Dummy","Dummy (2)","Player 3 (2)"]);
+    }
+
+    #[tokio::test]
+    async fn engine_assigns_free_ids() {
+        let log = Log::default();
+        let mut game = DurakGame::new();
+        for preferred in [7,7,3] {
+            game.add_player(TestPlayer::new(Plays::Forfeit).with_id(preferred).with_log(&log).boxed()).await.unwrap();
+        }
+        assert_eq!(game.player_info().iter().map(|info| info.id).collect::<Vec<_>>(),vec![7,8,3]);
+        game.init(&mut StdRng::seed_from_u64(0)).unwrap();
+        game.run_game().await.unwrap();
+        let mut seated = log.lock().unwrap().seated.clone();
+        seated.sort_by_key(|&(_,seat)| seat);
+        assert_eq!(seated,vec![(7,0),(8,1),(3,2)]);
+    }
+
+    // plays a game with the given seat requests and returns the ids by seat, checking each player
+    // was told the seat they ended up in
+    async fn seat_game(seating: SeatingPolicy, requests: &[Option<usize>], seed: u64) -> Vec<u64> {
+        let log = Log::default();
+        let mut game = DurakGame::new().with_seating(seating).with_seats(4);
+        for (preferred,&seat) in (10..).zip(requests) {
+            game.add_player(TestPlayer::new(Plays::Forfeit).with_id(preferred).with_seat(seat).with_log(&log).boxed()).await.unwrap();
+        }
+        game.init(&mut StdRng::seed_from_u64(seed)).unwrap();
+        let ids: Vec<u64> = game.player_info().iter().map(|info| info.id).collect();
+        game.run_game().await.unwrap();
+        let mut seated = log.lock().unwrap().seated.clone();
+        seated.sort_by_key(|&(_,seat)| seat);
+        assert_eq!(seated,ids.iter().copied().zip(0..).collect::<Vec<_>>());
+        // only asked for a seat when choosing, and told how many there are
+        let offered = &log.lock().unwrap().offered;
+        assert!(offered.iter().all(|&seats| seats == 4));
+        assert_eq!(offered.len(),if seating == SeatingPolicy::Chosen { requests.len() } else { 0 });
+        ids
+    }
+
+    #[tokio::test]
+    async fn players_take_their_seats() {
+        let requests = [Some(2),Some(2),None,Some(0)];
+        assert_eq!(seat_game(SeatingPolicy::JoinOrder,&requests,0).await,vec![10,11,12,13]);
+        // first come first served, the rest fill the gaps in join order
+        assert_eq!(seat_game(SeatingPolicy::Chosen,&requests,0).await,vec![13,11,10,12]);
+        assert_eq!(seat_game(SeatingPolicy::Chosen,&[Some(9),None],0).await,vec![10,11]);
+        let mut orders = Vec::new();
+        for seed in 0..8 {
+            let mut ids = seat_game(SeatingPolicy::Random,&requests,seed).await;
+            orders.push(ids.clone());
+            ids.sort();
+            assert_eq!(ids,vec![10,11,12,13]);
+        }
+        orders.dedup();
+        assert!(orders.len() > 1);
+    }
+
+    #[test]
+    fn others_go_round_from_the_left() {
+        let mut state = GameState::new();
+        for id in 0..4 { state.add_player(id,Profile::default()).unwrap(); }
+        state.init(&mut StdRng::seed_from_u64(0)).unwrap();
+        let to_play = gen_to_play_state(&state);
+        let ids: Vec<u64> = to_play.others_from(2).map(|info| info.id).collect();
+        assert_eq!(ids,vec![3,0,1]);
+    }
+
+    async fn takeback_game(remote: bool) -> (Result<DurakGameResult,DurakError>,Seen,Vec<MoveEvent>) {
+        let log = Log::default();
+        let mut game = DurakGame::new().with_takeback(true);
+        game.add_player(TestPlayer::new(Plays::Forfeit).with_takeback(remote).with_log(&log).boxed()).await.unwrap();
+        game.add_player(Box::new(RandomPlayer { rng: StdRng::seed_from_u64(1), illegal: 0 })).await.unwrap();
+        game.init(&mut StdRng::seed_from_u64(0)).unwrap();
+        let result = game.run_game().await;
+        let log = log.lock().unwrap();
+        (result,log.seen.clone(),log.events.clone())
+    }
+
+    #[tokio::test]
+    async fn taking_back_rewinds_the_game() {
+        let (result,seen,events) = takeback_game(false).await;
+        assert_eq!(result.unwrap().stats.forfeited,Some(0));
+        assert_eq!(seen.len(),3);
+        // asked again with the hand and table from before the move
+        assert_eq!(seen[2],seen[0]);
+        assert!(seen[0].2);
+        assert!(events.contains(&MoveEvent::TookBack(0)));
+    }
+
+    #[tokio::test]
+    async fn no_takeback_with_remote_players() {
+        let (result,seen,events) = takeback_game(true).await;
+        assert!(matches!(result,Err(DurakError::InvalidMove { .. })));
+        assert_eq!(seen.len(),2);
+        assert!(!seen[0].2);
+        assert!(!events.contains(&MoveEvent::TookBack(0)));
+    }
+
+    // plays the first attack of a two player game with a stubborn attacker
+    async fn stubborn_attack(policy: IllegalMovePolicy, invalid: usize) -> (GameState,Result<(),DurakError>,Vec<String>) {
+        let log = Log::default();
+        let mut state = game_state(2,AttackerRule::All);
+        state.illegal_moves = policy;
+        let mut engines = vec![TestPlayer::default().with_invalid(invalid).with_log(&log).boxed(),TestPlayer::default().boxed()];
+        let result = state.play_turn(&mut engines).await;
+        let messages = log.lock().unwrap().messages.clone();
+        (state,result,messages)
+    }
+
+    #[tokio::test]
+    async fn invalid_move_aborts_by_default() {
+        let (_,result,messages) = stubborn_attack(IllegalMovePolicy::Abort,1).await;
+        assert!(matches!(result,Err(DurakError::InvalidMove { id: 0, .. })));
+        assert!(messages.is_empty());
+    }
+
+    #[tokio::test]
+    async fn invalid_move_is_reprompted() {
+        let (state,result,messages) = stubborn_attack(IllegalMovePolicy::Reprompt(3),2).await;
+        assert!(result.is_ok());
+        assert_eq!(messages.len(),2);
+        assert!(messages.iter().all(|msg| msg.ends_with("try again")));
+        // the player passed on its third try
+        assert_eq!(state.attackers_passed,vec![0]);
+        assert_eq!(state.turn_type,GameTurnType::EndRound(RoundEnd::Defended));
+    }
+
+    #[tokio::test]
+    async fn reprompts_run_out_into_a_pass() {
+        let (state,result,messages) = stubborn_attack(IllegalMovePolicy::Reprompt(2),5).await;
+        assert!(result.is_ok());
+        assert_eq!(messages.len(),3);
+        assert!(messages[2].ends_with("passing instead"));
+        assert_eq!(state.attackers_passed,vec![0]);
+    }
+
+    #[tokio::test]
+    async fn invalid_move_auto_passes() {
+        let (state,result,messages) = stubborn_attack(IllegalMovePolicy::AutoPass,5).await;
+        assert!(result.is_ok());
+        assert_eq!(messages.len(),1);
+        assert_eq!(state.attackers_passed,vec![0]);
+        assert!(state.table.is_empty());
+    }
+
+    #[tokio::test]
+    async fn invalid_move_forfeits() {
+        let (mut state,result,messages) = stubborn_attack(IllegalMovePolicy::Forfeit,1).await;
+        assert!(result.is_ok());
+        assert!(messages[0].ends_with("you forfeit the game"));
+        assert_eq!(state.turn_type,GameTurnType::GameEnd);
+        assert_eq!(state.finish(),vec![Outcome::Lost,Outcome::Won]);
+        assert_eq!((state.stats.durak,state.stats.forfeited),(Some(0),Some(0)));
+        assert!(!state.stats.draw);
+    }
+
+    fn leaving_engines(num_players: usize, leaver: usize) -> Vec<Box<dyn DurakPlayer>> {
+        (0..num_players).map(|ind| match ind == leaver {
+            true => TestPlayer::new(Plays::Forfeit).boxed(),
+            false => TestPlayer::default().boxed(),
+        }).collect()
+    }
+
+    #[tokio::test]
+    async fn leaver_is_durak_by_default() {
+        let mut state = game_state(3,AttackerRule::All);
+        state.play_turn(&mut leaving_engines(3,0)).await.unwrap();
+        assert_eq!(state.turn_type,GameTurnType::GameEnd);
+        assert_eq!(state.finish(),vec![Outcome::Lost,Outcome::Won,Outcome::Won]);
+        assert_eq!((state.stats.durak,state.stats.forfeited),(Some(0),Some(0)));
+    }
+
+    #[tokio::test]
+    async fn leaving_attacker_cards_are_discarded() {
+        let mut state = game_state(3,AttackerRule::All);
+        state.rules.leave = LeaveRule::Discard;
+        state.play_turn(&mut leaving_engines(3,0)).await.unwrap();
+        assert!(state.players[0].hand.is_empty());
+        assert_eq!((state.left.clone(),state.discarded_cards.len()),(vec![0],6));
+        state.check_cards().unwrap();
+        // the leaver's turn passes on to the next attacker
+        assert_eq!((state.turn_type,state.to_play),(GameTurnType::Attack,2));
+    }
+
+    #[tokio::test]
+    async fn leaving_defender_ends_round_without_them() {
+        let mut state = game_state(3,AttackerRule::All);
+        state.rules.leave = LeaveRule::Discard;
+        deal(&mut state,&[&[0,1],&[10,11],&[20,21]]);
+        attack(&mut state,0,&[0]);
+        let mut engines = leaving_engines(3,1);
+        state.play_turn(&mut engines).await.unwrap();
+        assert_eq!(state.turn_type,GameTurnType::EndRound(RoundEnd::Defended));
+        state.play_turn(&mut engines).await.unwrap();
+        // the table is discarded and the two players left play on
+        assert!(state.table.is_empty());
+        assert_eq!((state.attackers.clone(),state.defender),(vec![2],0));
+        assert!(state.players[1].hand.is_empty());
+    }
+
+    #[tokio::test]
+    async fn last_player_left_wins() {
+        let mut state = game_state(2,AttackerRule::All);
+        state.rules.leave = LeaveRule::Discard;
+        let mut engines = leaving_engines(2,0);
+        while state.turn_type != GameTurnType::GameEnd {
+            state.play_turn(&mut engines).await.unwrap();
+        }
+        assert_eq!(state.finish(),vec![Outcome::Lost,Outcome::Won]);
+        assert_eq!((state.stats.durak,state.stats.left.clone()),(None,vec![0]));
+        assert!(!state.stats.draw);
+    }
+
+    #[tokio::test]
+    async fn observers_fail_and_time_out_alone() {
+        let log = Log::default();
+        let mut state = game_state(3,AttackerRule::All);
+        state.observe_timeout = Duration::from_millis(50);
+        let mut engines = vec![TestPlayer::default().slow().boxed(),TestPlayer::new(Plays::Fail).boxed(),TestPlayer::default().with_log(&log).boxed()];
+        state.broadcast(&mut engines,Some("hello"),&[]).await;
+        state.broadcast(&mut engines,None,&[MoveEvent::RoundDiscarded]).await;
+        assert_eq!(log.lock().unwrap().observed,2);
+    }
+
+    #[tokio::test]
+    async fn taking_records_move_events() {
+        let mut state = game_state(2,AttackerRule::All);
+        let lead = state.players[0].hand[0];
+        remove_card(&mut state.players[0].hand,&lead);
+        state.table.add_attack(lead);
+        state.advance(RoundEvent::Attacked).unwrap();
+        // defense, pile on and the end of the round
+        let mut engines = engines(2);
+        for _ in 0..3 { state.play_turn(&mut engines).await.unwrap(); }
+        assert_eq!(state.events,vec![MoveEvent::Passed(1),MoveEvent::TookCards(1,1),MoveEvent::Drew(0,1)]);
+        assert_eq!(MoveEvent::TookCards(1,1).to_string(),"Player 1 took 1 cards");
+    }
+
+    #[test]
+    fn taking_counts_beaten_cards() {
+        let mut state = game_state(2,AttackerRule::All);
+        deal(&mut state,&[&[0],&[10]]);
+        let card = |i| Card::try_from(i).unwrap();
+        state.table.add_attack(card(2));
+        assert!(state.table.add_defense(card(3),card(2)));
+        state.table.add_attack(card(4));
+        state.end_round(RoundEnd::Taken);
+        assert_eq!(state.events,vec![MoveEvent::TookCards(1,3)]);
+        assert_eq!(state.players[1].hand.len(),4);
+    }
+
+    #[tokio::test]
+    async fn player_errors_name_the_player() {
+        let mut state = game_state(2,AttackerRule::All);
+        let mut engines = vec![TestPlayer::new(Plays::Fail).boxed(),TestPlayer::default().boxed()];
+        match state.play_turn(&mut engines).await {
+            Err(DurakError::PlayerError { id, source }) => {
+                assert_eq!(id,0);
+                assert_eq!(source.to_string(),"Connection reset");
+            },
+            other => panic!("Expected a player error, got {:?}",other.err()),
+        }
+    }
+
+    #[tokio::test]
+    async fn game_ends_when_one_team_is_left() {
+        let mut state = team_state(vec![0,1,0,1]);
+        state.draw_pile.clear();
+        state.players[1].hand.clear();
+        assert_eq!(state.sides_left(),2);
+        assert_eq!(state.losing_team(),None);
+        state.players[3].hand.clear();
+        assert_eq!(state.sides_left(),1);
+        assert_eq!(state.losing_team(),Some(0));
+        next_round(&mut state,&mut engines(4)).await;
+        assert!(state.turn_type == GameTurnType::GameEnd);
+    }
+
+    #[tokio::test]
+    async fn team_rotation_skips_partners() {
+        let mut state = team_state(vec![0,1,0,1]);
+        state.draw_pile.clear();
+        state.players[2].hand.clear();
+        next_round(&mut state,&mut engines(4)).await;
+        // player 1 defended, so leads against the next opponent with cards
+        assert_eq!((state.to_play,state.defender),(1,0));
+        assert_eq!(state.attackers,vec![1,3]);
+    }
+
+    // empties every hand and the talon, then gives each seat the listed cards
+    fn deal(state: &mut GameState, hands: &[&[usize]]) {
+        state.draw_pile.clear();
+        state.table = Table::new();
+        for (player,hand) in state.players.iter_mut().zip(hands) {
+            player.hand = hand.iter().map(|&i| Card::try_from(i).unwrap()).collect();
+        }
+    }
+
+    #[tokio::test]
+    async fn last_two_out_together_is_a_draw() {
+        let mut state = game_state(2,AttackerRule::All);
+        deal(&mut state,&[&[],&[]]);
+        state.table.add_attack(Card::try_from(0).unwrap());
+        state.table.add_defense(Card::try_from(1).unwrap(),Card::try_from(0).unwrap());
+        next_round(&mut state,&mut engines(2)).await;
+        assert!(state.turn_type == GameTurnType::GameEnd);
+        assert_eq!(state.stats.finish_order,vec![0,1]);
+        assert_eq!(state.finish(),vec![Outcome::Draw,Outcome::Draw]);
+        assert!(state.stats.draw);
+        assert_eq!(state.stats.durak,None);
+    }
+
+    #[tokio::test]
+    async fn last_player_holding_cards_is_durak() {
+        let mut state = game_state(3,AttackerRule::All);
+        deal(&mut state,&[&[],&[5],&[]]);
+        next_round(&mut state,&mut engines(3)).await;
+        assert!(state.turn_type == GameTurnType::GameEnd);
+        assert_eq!(state.finish(),vec![Outcome::Won,Outcome::Lost,Outcome::Won]);
+        assert_eq!(state.stats.durak,Some(1));
+        assert!(!state.stats.draw);
+    }
+
+    #[tokio::test]
+    async fn defender_taking_the_last_cards_is_durak() {
+        let mut state = game_state(2,AttackerRule::All);
+        deal(&mut state,&[&[],&[]]);
+        state.table.add_attack(Card::try_from(0).unwrap());
+        state.turn_type = GameTurnType::EndRound(RoundEnd::Taken);
+        state.play_turn(&mut engines(2)).await.unwrap();
+        assert!(state.turn_type == GameTurnType::GameEnd);
+        assert_eq!(state.players[1].hand.len(),1);
+        assert_eq!(state.finish(),vec![Outcome::Won,Outcome::Lost]);
+    }
+
+    #[tokio::test]
+    async fn game_goes_on_while_two_players_hold_cards() {
+        let mut state = game_state(3,AttackerRule::All);
+        deal(&mut state,&[&[],&[5],&[6]]);
+        next_round(&mut state,&mut engines(3)).await;
+        assert!(state.turn_type == GameTurnType::Attack);
+        assert_eq!(state.stats.finish_order,vec![0]);
+        assert_eq!((state.to_play,state.defender),(1,2));
+    }
+
+    #[tokio::test]
+    async fn round_limit_is_a_draw() {
+        let mut state = game_state(2,AttackerRule::All);
+        state.round_limit = Some(1);
+        next_round(&mut state,&mut engines(2)).await;
+        assert!(state.turn_type == GameTurnType::GameEnd);
+        assert_eq!(state.finish(),vec![Outcome::Draw,Outcome::Draw]);
+    }
+
+    fn card(ind: usize) -> Card {
+        Card::try_from(ind).unwrap()
+    }
+
+    // puts attack cards from `attacker` on the table and moves the round on
+    fn attack(state: &mut GameState, attacker: usize, attacks: &[usize]) {
+        for &ind in attacks {
+            remove_card(&mut state.players[attacker].hand,&card(ind));
+            state.table.add_attack(card(ind));
+        }
+        state.advance(RoundEvent::Attacked).unwrap();
+    }
+
+    // beats `against` with the defender's `defense` card and moves the round on
+    fn beat(state: &mut GameState, defense: usize, against: usize) {
+        remove_card(&mut state.players[state.defender].hand,&card(defense));
+        assert!(state.table.add_defense(card(defense),card(against)));
+        state.advance(RoundEvent::Beat).unwrap();
+    }
+
+    #[test]
+    fn attack_moves_to_defense() {
+        let mut state = game_state(3,AttackerRule::All);
+        deal(&mut state,&[&[0,1],&[10,11],&[20,21]]);
+        attack(&mut state,0,&[0]);
+        assert_eq!(state.turn_type,GameTurnType::Defense);
+        assert_eq!((state.to_play,state.last_attacker),(1,0));
+    }
+
+    #[test]
+    fn pass_moves_to_next_attacker() {
+        let mut state = game_state(3,AttackerRule::All);
+        deal(&mut state,&[&[0,1],&[10,11],&[20,21]]);
+        state.advance(RoundEvent::AttackerPassed).unwrap();
+        assert_eq!(state.turn_type,GameTurnType::Attack);
+        assert_eq!(state.to_play,2);
+        state.advance(RoundEvent::AttackerPassed).unwrap();
+        assert_eq!(state.turn_type,GameTurnType::EndRound(RoundEnd::Defended));
+    }
+
+    #[test]
+    fn pass_after_attack_asks_others_again() {
+        let mut state = game_state(3,AttackerRule::All);
+        deal(&mut state,&[&[0,1],&[10,11,12],&[20,21]]);
+        state.advance(RoundEvent::AttackerPassed).unwrap();
+        attack(&mut state,2,&[20]);
+        beat(&mut state,10,20);
+        assert_eq!(state.attackers_passed,Vec::<usize>::new());
+        assert_eq!(state.turn_type,GameTurnType::Attack);
+        assert_eq!(state.to_play,2);
+        state.advance(RoundEvent::AttackerPassed).unwrap();
+        assert_eq!(state.to_play,0);
+    }
+
+    #[test]
+    fn take_goes_through_pile_on() {
+        let mut state = game_state(3,AttackerRule::All);
+        deal(&mut state,&[&[0,1],&[10,11],&[20,21]]);
+        attack(&mut state,0,&[0]);
+        state.advance(RoundEvent::DefenderTook).unwrap();
+        assert_eq!(state.turn_type,GameTurnType::PileOn);
+        state.table.add_attack(card(20));
+        state.advance(RoundEvent::PiledOn).unwrap();
+        assert_eq!(state.turn_type,GameTurnType::EndRound(RoundEnd::Taken));
+        state.end_round(RoundEnd::Taken);
+        assert_eq!(state.players[1].hand.len(),4);
+        assert!(state.table.is_empty());
+        // the defender is skipped as the next first attacker
+        assert_eq!((state.to_play,state.defender),(2,0));
+        assert_eq!(state.turn_type,GameTurnType::Attack);
+    }
+
+    #[test]
+    fn beat_all_returns_to_last_attacker() {
+        let mut state = game_state(3,AttackerRule::All);
+        deal(&mut state,&[&[0,1],&[10,11,12],&[20,21]]);
+        attack(&mut state,0,&[0]);
+        beat(&mut state,10,0);
+        assert_eq!(state.turn_type,GameTurnType::Attack);
+        assert_eq!(state.to_play,0);
+    }
+
+    #[test]
+    fn open_attacks_keep_defender_defending() {
+        let mut state = game_state(3,AttackerRule::All);
+        deal(&mut state,&[&[0,9],&[10,11,12],&[20,21]]);
+        attack(&mut state,0,&[0,9]);
+        beat(&mut state,10,9);
+        assert_eq!(state.turn_type,GameTurnType::Defense);
+        assert_eq!(state.to_play,1);
+        beat(&mut state,11,0);
+        assert_eq!(state.turn_type,GameTurnType::Attack);
+    }
+
+    #[test]
+    fn beat_all_on_full_table_ends_round() {
+        let mut state = game_state(3,AttackerRule::All);
+        deal(&mut state,&[&[0,1,2,3,4,5,6],&[10,11,12,13,14,15,16],&[20,21]]);
+        attack(&mut state,0,&[0,1,2,3,4,5]);
+        for i in 0..5 { beat(&mut state,10 + i,i); }
+        assert_eq!(state.turn_type,GameTurnType::Defense);
+        beat(&mut state,15,5);
+        assert_eq!(state.turn_type,GameTurnType::EndRound(RoundEnd::Defended));
+    }
+
+    #[test]
+    fn defender_running_out_ends_round() {
+        let mut state = game_state(3,AttackerRule::All);
+        deal(&mut state,&[&[0,1],&[10,11],&[20,21]]);
+        attack(&mut state,0,&[0,1]);
+        beat(&mut state,10,0);
+        assert_eq!(state.turn_type,GameTurnType::Defense);
+        beat(&mut state,11,1);
+        assert_eq!(state.turn_type,GameTurnType::EndRound(RoundEnd::Defended));
+    }
+
+    #[test]
+    fn no_more_room_than_defender_cards() {
+        let mut state = game_state(3,AttackerRule::All);
+        deal(&mut state,&[&[0,1],&[10,11],&[20,21]]);
+        // one card left to beat the open attack with, so nothing more can be thrown in
+        attack(&mut state,0,&[0]);
+        state.table.add_attack(card(20));
+        remove_card(&mut state.players[2].hand,&card(20));
+        beat(&mut state,10,0);
+        assert_eq!(state.turn_type,GameTurnType::Defense);
+        assert_eq!(state.attack_room(),0);
+    }
+
+    #[test]
+    fn attacker_running_out_is_skipped() {
+        let mut state = game_state(3,AttackerRule::All);
+        deal(&mut state,&[&[0],&[10,11],&[20,21]]);
+        attack(&mut state,0,&[0]);
+        beat(&mut state,10,0);
+        assert_eq!(state.turn_type,GameTurnType::Attack);
+        assert_eq!(state.to_play,2);
+    }
+
+    #[test]
+    fn beat_all_discards_table() {
+        let mut state = game_state(3,AttackerRule::All);
+        deal(&mut state,&[&[0,1],&[10,11],&[20,21]]);
+        attack(&mut state,0,&[0]);
+        beat(&mut state,10,0);
+        state.end_round(RoundEnd::Defended);
+        assert_eq!(state.discarded_cards,vec![card(0),card(10)]);
+        assert_eq!((state.to_play,state.defender),(1,2));
+        assert_eq!(state.stats.rounds,1);
+    }
+
+    #[test]
+    fn refill_stops_when_talon_runs_out() {
+        let mut state = game_state(3,AttackerRule::All);
+        deal(&mut state,&[&[0,1,2,3],&[10,11,12],&[20,21,22,23,24]]);
+        state.draw_pile = vec![card(30),card(31),card(32)];
+        state.end_round(RoundEnd::Defended);
+        // first attacker first, then clockwise, defender last
+        assert_eq!(state.players[0].hand.len(),6);
+        assert_eq!(state.players[2].hand.len(),6);
+        assert_eq!(state.players[1].hand.len(),3);
+        assert!(state.draw_pile.is_empty());
+    }
+
+    #[test]
+    fn empty_talon_leaves_hands_as_they_are() {
+        let mut state = game_state(3,AttackerRule::All);
+        deal(&mut state,&[&[],&[10],&[20,21]]);
+        state.end_round(RoundEnd::Defended);
+        assert_eq!(state.players.iter().map(|p| p.hand.len()).collect::<Vec<_>>(),vec![0,1,2]);
+        assert_eq!(state.stats.finish_order,vec![0]);
+        assert_eq!((state.to_play,state.defender),(1,2));
+        assert_eq!(state.attackers,vec![1]);
+    }
+
+    #[test]
+    fn transition_out_of_turn_is_an_error() {
+        let mut state = game_state(3,AttackerRule::All);
+        assert!(state.advance(RoundEvent::Beat).is_err());
+        assert!(state.advance(RoundEvent::PiledOn).is_err());
+        attack(&mut state,0,&[]);
+        assert!(state.advance(RoundEvent::AttackerPassed).is_err());
+    }
+
+    #[tokio::test]
+    async fn draw_notifies_won_by_default() {
+        let log = Log::default();
+        let mut state = game_state(2,AttackerRule::All);
+        deal(&mut state,&[&[],&[]]);
+        state.turn_type = GameTurnType::GameEnd;
+        let game = DurakGame {
+            state,
+            engines: (0..2).map(|_| TestPlayer::default().with_log(&log).boxed()).collect(),
+            seating: SeatingPolicy::JoinOrder,
+            seats: 6,
+            seat_requests: Vec::new(),
+            history: Vec::new(),
+        };
+        let result = game.run_game().await.unwrap();
+        assert!(result.is_draw());
+        assert_eq!(result.drawn.len(),2);
+        assert_eq!(log.lock().unwrap().outcomes,vec!["won","won"]);
+    }
+
+    // makes random legal moves, or a random and most likely illegal one `illegal` times in 100
+    struct RandomPlayer {
+        rng: StdRng,
+        illegal: u32,
+    }
+
+    impl RandomPlayer {
+        fn plays_illegal(&mut self) -> bool {
+            self.rng.gen_range(0..100) < self.illegal
+        }
+
+        fn any_card(&mut self) -> Card {
+            Card::try_from(self.rng.gen_range(0..36)).unwrap()
+        }
+    }
+
+    #[async_trait]
+    impl DurakPlayer for RandomPlayer {
+        async fn attack(&mut self, state: &ToPlayState) -> Result<Action> {
+            if self.plays_illegal() { return Ok(Action::Play(self.any_card())); }
+            let playable: Vec<Card> = state.hand.iter().copied().filter(|&c| state.validate_attack(&Action::Play(c)).is_ok()).collect();
+            if playable.is_empty() || self.rng.gen_range(0..4) == 0 { return Ok(Action::Pass); }
+            let card = playable[self.rng.gen_range(0..playable.len())];
+            if self.rng.gen_bool(0.5) {
+                return Ok(Action::Play(card));
+            }
+            // every playable card of the rank at once
+            Ok(Action::attack_with(playable.into_iter().filter(|c| c.rank == card.rank).take(state.attack_limit()).collect()))
+        }
+
+        async fn defend(&mut self, state: &ToPlayState) -> Result<Action> {
+            if self.plays_illegal() { return Ok(Action::Defend { card: self.any_card(), against: self.any_card() }); }
+            let defenses: Vec<Action> = state.hand.iter()
+                .flat_map(|&card| state.beatable_attacks(&card).into_iter().map(move |against| Action::Defend { card, against }))
+                .collect();
+            if defenses.is_empty() || self.rng.gen_range(0..4) == 0 { return Ok(Action::Pass); }
+            Ok(defenses[self.rng.gen_range(0..defenses.len())].clone())
+        }
+
+        async fn pile_on(&mut self, state: &ToPlayState) -> Result<Vec<Card>> {
+            if self.plays_illegal() { return Ok(vec![self.any_card()]); }
+            Ok(state.hand.iter().copied().filter(|c| state.validate_pile_on_single(c).is_ok() && self.rng.gen_bool(0.5)).take(state.attack_limit()).collect())
+        }
+
+        async fn exchange_trump(&mut self, _state: &ToPlayState) -> Result<bool> {
+            Ok(self.rng.gen_bool(0.5))
+        }
+    }
+
+    const ROUND_LIMIT: usize = 200;
+    // at most 6 attacks and 6 defenses a round, with up to 5 attackers passing around each attack,
+    // plus the pile on and the end of the round
+    const TURNS_PER_ROUND: usize = 6 + 6 + 7 * 5 + 2;
+
+    proptest! {
+        #[test]
+        fn engine_invariants(
+            seed in any::<u64>(),
+            num_players in 2usize..=6,
+            illegal in prop_oneof![Just(0u32),1u32..5],
+            attackers in prop_oneof![Just(AttackerRule::All),Just(AttackerRule::Neighbours),Just(AttackerRule::FirstThenOthers)],
+            trump_exchange in any::<bool>(),
+            teams in any::<bool>(),
+            illegal_moves in prop_oneof![Just(IllegalMovePolicy::Abort),Just(IllegalMovePolicy::Reprompt(2)),Just(IllegalMovePolicy::AutoPass),Just(IllegalMovePolicy::Forfeit)],
+            leave in prop_oneof![Just(LeaveRule::Durak),Just(LeaveRule::Discard)],
+        ) {
+            let teams = teams && num_players % 2 == 0;
+            let mut state = GameState::new();
+            state.rules = Rules::new().with_attackers(attackers).with_trump_exchange(trump_exchange).with_leave(leave);
+            if teams { state.rules.teams = Some((0..num_players).map(|seat| seat % 2).collect()); }
+            state.round_limit = Some(ROUND_LIMIT);
+            state.illegal_moves = illegal_moves;
+            for id in 0..num_players { state.add_player(id as u64,Profile::default()).unwrap(); }
+            state.init(&mut StdRng::seed_from_u64(seed)).unwrap();
+            let mut engines: Vec<Box<dyn DurakPlayer>> = (0..num_players)
+                .map(|ind| Box::new(RandomPlayer { rng: StdRng::seed_from_u64(seed ^ ind as u64), illegal }) as Box<dyn DurakPlayer>)
+                .collect();
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+
+            let mut turns = 0;
+            while state.turn_type != GameTurnType::GameEnd {
+                let played = runtime.block_on(state.play_turn(&mut engines));
+                if let Err(e) = state.check_cards() { prop_assert!(false,"{}",e); }
+                if let Err(e) = played {
+                    // illegal moves can end the game, but must be turned away before any card moves
+                    prop_assert!(illegal > 0 && illegal_moves == IllegalMovePolicy::Abort && matches!(e,DurakError::InvalidMove { .. }),"{}",e);
+                    return Ok(());
+                }
+                turns += 1;
+                prop_assert!(turns <= ROUND_LIMIT * TURNS_PER_ROUND);
+                prop_assert!(state.table.len() <= 6);
+                if state.turn_type == GameTurnType::Defense {
+                    prop_assert!(state.table.open_attacks().count() <= state.players[state.defender].hand.len());
+                }
+            }
+
+            let outcomes = state.finish();
+            let lost = outcomes.iter().filter(|&&outcome| outcome == Outcome::Lost).count();
+            let left = state.stats.left.len();
+            if state.stats.draw {
+                prop_assert!(outcomes.iter().enumerate().all(|(ind,&outcome)| outcome == Outcome::Draw || state.stats.left.contains(&ind)));
+            } else if state.stats.durak.is_none() && state.stats.losing_team.is_none() {
+                // everybody else left the game
+                prop_assert!(left > 0 && lost == left);
+            } else if teams {
+                prop_assert!(state.stats.losing_team.is_some() && lost > left);
+            } else {
+                prop_assert!(state.stats.durak.is_some() && lost == 1 + left);
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn playouts_keep_every_card(seed in any::<u64>(), num_players in 2usize..=6, turns in 0usize..200) {
+            let mut state = GameState::new();
+            for id in 0..num_players { state.add_player(id as u64,Profile::default()).unwrap(); }
+            state.init(&mut StdRng::seed_from_u64(seed)).unwrap();
+            let mut engines: Vec<Box<dyn DurakPlayer>> = (0..num_players)
+                .map(|ind| Box::new(RandomPlayer { rng: StdRng::seed_from_u64(seed ^ ind as u64), illegal: 0 }) as Box<dyn DurakPlayer>)
+                .collect();
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+            // play into the game and deal a playout from the next prompt
+            for _ in 0..turns {
+                runtime.block_on(state.play_turn(&mut engines)).unwrap();
+            }
+            while state.turn_type != GameTurnType::GameEnd && !state.prompts(state.to_play) {
+                runtime.block_on(state.play_turn(&mut engines)).unwrap();
+            }
+            if state.turn_type == GameTurnType::GameEnd { return Ok(()); }
+            let turn = if state.turn_type == GameTurnType::Attack { Turn::Attack } else { Turn::Defense };
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut playout = Playout::deal(&gen_to_play_state(&state),turn,&state.rules,&[],&state.discarded_cards,&mut rng);
+            prop_assert_eq!(playout.turn(),Some((state.to_play,turn)));
+            prop_assert_eq!(playout.hand(state.to_play),&state.players[state.to_play].hand[..]);
+            for ind in 0..num_players { prop_assert_eq!(playout.hand(ind).len(),state.players[ind].hand.len()); }
+            prop_assert_eq!(playout.state.draw_pile.len(),state.draw_pile.len());
+            // attackers who ran out of cards this round can't be told from players who were out before
+            let attacking = |state: &GameState| {
+                let mut attackers: Vec<usize> = state.attackers.iter().copied().filter(|&ind| !state.players[ind].hand.is_empty()).collect();
+                attackers.sort();
+                attackers
+            };
+            prop_assert_eq!(attacking(&playout.state),attacking(&state));
+
+            let mut moves = 0;
+            while let Some((seat,turn)) = playout.turn() {
+                let view = playout.view().to_static();
+                let action = match turn {
+                    Turn::Attack => runtime.block_on(engines[seat].attack(&view)).unwrap(),
+                    Turn::Defense => runtime.block_on(engines[seat].defend(&view)).unwrap(),
+                    Turn::PileOn => Action::attack_with(runtime.block_on(engines[seat].pile_on(&view)).unwrap()),
+                };
+                if let Err(e) = playout.play(action) { prop_assert!(false,"{}",e); }
+                if let Err(e) = playout.state.check_cards() { prop_assert!(false,"{}",e); }
+                moves += 1;
+                if moves > ROUND_LIMIT * TURNS_PER_ROUND { break; }
+            }
+            if playout.turn().is_none() { prop_assert!(playout.score(0).is_some()); }
+        }
+    }
+}
@@ -13,7 +13,11 @@
 #![warn(missing_docs)]
 
 pub mod game;
+pub mod error;
 pub mod card;
+pub mod table;
+pub mod rules;
 pub mod toplaystate;
+pub mod rating;
 pub mod prelude;
 
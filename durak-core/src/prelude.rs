@@ -1,5 +1,8 @@
 //! Durak prelude
 
-pub use super::game::{DurakPlayer, DurakGame, Action, Ready};
+pub use super::game::{DurakPlayer, DurakGame, DurakGameResult, GameStats, Action, MoveEvent, Ready, IllegalMovePolicy, SeatingPolicy, Playout, Turn};
 pub use super::card::{Card, Suit, Rank, hand_fmt, sort_cards};
-pub use super::toplaystate::{PlayerInfo, ToPlayState};
+pub use super::toplaystate::{PlayerInfo, Profile, ToPlayState};
+pub use super::table::{Table, TableSlot};
+pub use super::error::DurakError;
+pub use super::rules::{Rules, AttackerRule, FirstAttackerRule, LeaveRule};
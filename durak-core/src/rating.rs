@@ -0,0 +1,199 @@
+//! Elo style player ratings.
+//!
+//! Durak has a single loser, so a game is scored as a set of head to head results between each
+//! player that got out and the durak. Players that got out are not compared with each other, and
+//! a game without a durak leaves ratings unchanged. Ratings are keyed by name so bots and people
+//! can share a leaderboard, and can be kept between runs in a small JSON file. When several seats
+//! in a game share a name, e.g. two copies of a bot, each seat is rated on its own and the changes
+//! are added up, counting as one game for that name.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Serialize,Deserialize};
+
+/// Rating a new player starts with.
+pub const INITIAL_RATING: f64 = 1500.0;
+
+/// Default maximum rating change for a single game.
+pub const DEFAULT_K_FACTOR: f64 = 32.0;
+
+/// A single player's rating and record.
+#[derive(Serialize,Deserialize,Clone,Debug)]
+pub struct Rating {
+    /// Current rating.
+    pub rating: f64,
+    /// Number of games played.
+    pub games: u64,
+    /// Number of games where this player was the durak.
+    pub duraks: u64,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Rating {
+            rating: INITIAL_RATING,
+            games: 0,
+            duraks: 0,
+        }
+    }
+}
+
+/// A collection of ratings keyed by player name.
+#[derive(Serialize,Deserialize,Clone,Debug)]
+pub struct Ratings {
+    k_factor: f64,
+    players: BTreeMap<String,Rating>,
+}
+
+impl Default for Ratings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// expected score of a player rated `a` against a player rated `b`
+fn expected_score(a: f64, b: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((b - a) / 400.0))
+}
+
+impl Ratings {
+    /// Creates an empty set of ratings.
+    pub fn new() -> Self {
+        Ratings {
+            k_factor: DEFAULT_K_FACTOR,
+            players: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the maximum rating change for a single game.
+    pub fn with_k_factor(mut self, k_factor: f64) -> Self {
+        self.k_factor = k_factor;
+        self
+    }
+
+    /// Loads ratings from a JSON file. A missing file gives an empty set of ratings.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Saves ratings to a JSON file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        std::fs::write(path,serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Rating for a player, or the initial rating if they have not played yet.
+    pub fn rating(&self, name: &str) -> f64 {
+        self.players.get(name).map(|r| r.rating).unwrap_or(INITIAL_RATING)
+    }
+
+    /// Full record for a player.
+    pub fn get(&self, name: &str) -> Option<&Rating> {
+        self.players.get(name)
+    }
+
+    /// Updates ratings from the result of one game.
+    /// `finish_order` lists the players that got out, in the order they got out. `durak` is the
+    /// loser, or `None` if the game was a draw.
+    pub fn record_game<S: AsRef<str>>(&mut self, finish_order: &[S], durak: Option<&str>) {
+        // changes by name, worked out seat by seat from the ratings before the game
+        let mut changes: BTreeMap<&str,f64> = finish_order.iter().map(|name| (name.as_ref(),0.0)).collect();
+        if let Some(durak) = durak {
+            let durak_rating = self.rating(durak);
+            changes.entry(durak).or_default();
+            // split the durak's games so a loss costs at most one K factor
+            let k = self.k_factor / finish_order.len().max(1) as f64;
+            for name in finish_order {
+                let change = k * (1.0 - expected_score(self.rating(name.as_ref()),durak_rating));
+                *changes.entry(name.as_ref()).or_default() += change;
+                *changes.entry(durak).or_default() -= change;
+            }
+        }
+        for (name,change) in changes {
+            let rating = self.players.entry(name.to_owned()).or_default();
+            rating.rating += change;
+            rating.games += 1;
+            if durak == Some(name) { rating.duraks += 1; }
+        }
+    }
+
+    /// All players sorted from highest to lowest rating.
+    pub fn leaderboard(&self) -> Vec<(&str,&Rating)> {
+        let mut board: Vec<_> = self.players.iter().map(|(name,rating)| (name.as_str(),rating)).collect();
+        board.sort_by(|a,b| b.1.rating.total_cmp(&a.1.rating));
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn expected_scores() {
+        assert!(close(expected_score(1500.0,1500.0),0.5));
+        assert!(close(expected_score(1900.0,1500.0),10.0 / 11.0));
+        assert!(close(expected_score(1500.0,1900.0) + expected_score(1900.0,1500.0),1.0));
+    }
+
+    #[test]
+    fn durak_loses_one_k_factor() {
+        let mut ratings = Ratings::new();
+        ratings.record_game(&["a"],Some("b"));
+        assert!(close(ratings.rating("a"),INITIAL_RATING + 16.0));
+        assert!(close(ratings.rating("b"),INITIAL_RATING - 16.0));
+
+        // the loss is split between everyone who got out
+        let mut ratings = Ratings::new().with_k_factor(10.0);
+        ratings.record_game(&["a","b"],Some("c"));
+        assert!(close(ratings.rating("a"),INITIAL_RATING + 2.5));
+        assert!(close(ratings.rating("b"),INITIAL_RATING + 2.5));
+        assert!(close(ratings.rating("c"),INITIAL_RATING - 5.0));
+        assert_eq!(ratings.get("c").map(|r| (r.games,r.duraks)),Some((1,1)));
+    }
+
+    #[test]
+    fn draws_only_count_games() {
+        let mut ratings = Ratings::new();
+        ratings.record_game(&["a","b"],None);
+        assert!(close(ratings.rating("a"),INITIAL_RATING));
+        assert_eq!(ratings.get("b").map(|r| (r.games,r.duraks)),Some((1,0)));
+    }
+
+    #[test]
+    fn shared_names_add_up() {
+        let mut ratings = Ratings::new();
+        // one copy of a beat the other, and b beat the copy that lost
+        ratings.record_game(&["a","b"],Some("a"));
+        assert!(close(ratings.rating("a"),INITIAL_RATING - 8.0));
+        assert!(close(ratings.rating("b"),INITIAL_RATING + 8.0));
+        assert_eq!(ratings.get("a").map(|r| (r.games,r.duraks)),Some((1,1)));
+        assert_eq!(ratings.get("b").map(|r| (r.games,r.duraks)),Some((1,0)));
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path = std::env::temp_dir().join(format!("durak-ratings-{}.json",std::process::id()));
+        assert!(Ratings::load(&path).unwrap().leaderboard().is_empty());
+        let mut ratings = Ratings::new().with_k_factor(20.0);
+        ratings.record_game(&["a"],Some("b"));
+        ratings.save(&path).unwrap();
+        let mut loaded = Ratings::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(close(loaded.rating("a"),ratings.rating("a")));
+        assert_eq!(loaded.get("b").map(|r| (r.games,r.duraks)),Some((1,1)));
+        // the K factor is kept too
+        loaded.record_game(&["c"],Some("d"));
+        assert!(close(loaded.rating("c"),INITIAL_RATING + 10.0));
+    }
+}
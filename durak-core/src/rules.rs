@@ -0,0 +1,92 @@
+//! Optional house rules.
+
+use serde::{Serialize,Deserialize};
+
+/// Which players may attack the defender.
+#[derive(Serialize,Deserialize,Copy,Clone,PartialEq,Eq,Debug,Default)]
+pub enum AttackerRule {
+    /// Every other player may attack, in turn order.
+    #[default]
+    All,
+    /// Only the players sitting directly either side of the defender may attack.
+    Neighbours,
+    /// Only the first attacker may lead the round, after that everyone else may throw in.
+    FirstThenOthers,
+}
+
+/// Who attacks first in the first round.
+#[derive(Serialize,Deserialize,Copy,Clone,PartialEq,Eq,Debug,Default)]
+pub enum FirstAttackerRule {
+    /// The player holding the lowest trump leads, and reveals it. Falls back to seat order if
+    /// nobody was dealt a trump.
+    #[default]
+    LowestTrump,
+    /// The first player to join leads.
+    SeatOrder,
+    /// A random player leads.
+    Random,
+}
+
+/// What happens to a player who leaves in the middle of a game.
+#[derive(Serialize,Deserialize,Copy,Clone,PartialEq,Eq,Debug,Default)]
+pub enum LeaveRule {
+    /// The player who leaves is the durak and the game ends.
+    #[default]
+    Durak,
+    /// The player who leaves loses, their cards are discarded and everyone else plays on.
+    Discard,
+}
+
+/// The rule options for a game. The defaults are the rules described in the README.
+#[derive(Serialize,Deserialize,Clone,PartialEq,Debug,Default)]
+pub struct Rules {
+    /// Which players may attack the defender.
+    pub attackers: AttackerRule,
+    /// Who attacks first in the first round.
+    pub first_attacker: FirstAttackerRule,
+    /// Team number for each seat, for playing in partnerships. Teammates never attack each other
+    /// and win or lose together. Partners usually sit across from each other, e.g. `[0,1,0,1]`.
+    pub teams: Option<Vec<usize>>,
+    /// Whether the holder of the six of trumps may swap it for the face up trump card at the
+    /// bottom of the talon.
+    pub trump_exchange: bool,
+    /// What happens to a player who leaves in the middle of a game.
+    pub leave: LeaveRule,
+}
+
+impl Rules {
+    /// The default rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets which players may attack the defender.
+    pub fn with_attackers(mut self, attackers: AttackerRule) -> Self {
+        self.attackers = attackers;
+        self
+    }
+
+    /// Sets who attacks first in the first round.
+    pub fn with_first_attacker(mut self, first_attacker: FirstAttackerRule) -> Self {
+        self.first_attacker = first_attacker;
+        self
+    }
+
+    /// Sets the team number for each seat.
+    pub fn with_teams(mut self, teams: Vec<usize>) -> Self {
+        self.teams = Some(teams);
+        self
+    }
+
+    /// Sets whether the six of trumps may be exchanged for the face up trump card.
+    pub fn with_trump_exchange(mut self, trump_exchange: bool) -> Self {
+        self.trump_exchange = trump_exchange;
+        self
+    }
+
+    /// Sets what happens to a player who leaves in the middle of a game.
+    pub fn with_leave(mut self, leave: LeaveRule) -> Self {
+        self.leave = leave;
+        self
+    }
+}
@@ -0,0 +1,105 @@
+//! The cards in play during a round.
+
+use serde::{Serialize,Deserialize};
+
+use crate::card::{Card,Rank};
+
+/// An attack card and the card that beat it, if it has been beaten.
+#[derive(Serialize,Deserialize,Copy,Clone,PartialEq,Debug)]
+pub struct TableSlot {
+    /// The attack card.
+    pub attack: Card,
+    /// The defense card that beat the attack, if any.
+    pub defense: Option<Card>,
+}
+
+/// All the cards played so far this round, with each attack paired with its defense.
+#[derive(Serialize,Deserialize,Clone,PartialEq,Debug,Default)]
+#[serde(transparent)]
+pub struct Table {
+    slots: Vec<TableSlot>,
+}
+
+impl Table {
+    /// Creates an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All slots in the order the attacks were played.
+    pub fn slots(&self) -> &[TableSlot] {
+        &self.slots
+    }
+
+    /// Number of attacks played.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Whether no attacks have been played.
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// All attack cards.
+    pub fn attacks(&self) -> impl Iterator<Item=&Card> + '_ {
+        self.slots.iter().map(|slot| &slot.attack)
+    }
+
+    /// All defense cards.
+    pub fn defenses(&self) -> impl Iterator<Item=&Card> + '_ {
+        self.slots.iter().filter_map(|slot| slot.defense.as_ref())
+    }
+
+    /// Attack cards that have not been beaten yet.
+    pub fn open_attacks(&self) -> impl Iterator<Item=&Card> + '_ {
+        self.slots.iter().filter(|slot| slot.defense.is_none()).map(|slot| &slot.attack)
+    }
+
+    /// Whether every attack has been beaten.
+    pub fn all_beaten(&self) -> bool {
+        self.slots.iter().all(|slot| slot.defense.is_some())
+    }
+
+    /// Every card on the table.
+    pub fn cards(&self) -> impl Iterator<Item=&Card> + '_ {
+        self.attacks().chain(self.defenses())
+    }
+
+    /// Whether a card is on the table.
+    pub fn contains(&self, card: &Card) -> bool {
+        self.cards().any(|c| c == card)
+    }
+
+    /// Whether a card of this rank has been played this round, as an attack or a defense.
+    pub fn has_rank(&self, rank: Rank) -> bool {
+        self.cards().any(|c| c.rank == rank)
+    }
+
+    /// Adds an unbeaten attack.
+    pub fn add_attack(&mut self, card: Card) {
+        self.slots.push(TableSlot {
+            attack: card,
+            defense: None,
+        });
+    }
+
+    /// Beats the open attack `against` with `card`. Returns false if `against` is not an open
+    /// attack.
+    pub fn add_defense(&mut self, card: Card, against: Card) -> bool {
+        match self.slots.iter_mut().find(|slot| slot.attack == against && slot.defense.is_none()) {
+            Some(slot) => {
+                slot.defense = Some(card);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Removes and returns all cards on the table.
+    pub fn take_all(&mut self) -> Vec<Card> {
+        let cards = self.cards().copied().collect();
+        self.slots.clear();
+        cards
+    }
+}
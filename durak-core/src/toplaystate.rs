@@ -1,20 +1,63 @@
 //! Limited game state and player information made available to players on their turn.
 
 use std::borrow::Cow;
+use std::fmt;
 
 use serde::{Serialize,Deserialize};
 use thiserror::Error;
 
 use crate::prelude::*;
 
+/// How a player is shown to the others, chosen when joining a game with [`DurakPlayer::join()`].
+#[derive(Serialize,Deserialize,Clone,PartialEq,Eq,Debug,Default)]
+pub struct Profile {
+    /// Display name, kept unique by the engine. Players without one are named after their ID.
+    pub name: String,
+    /// A short avatar to show next to the name, e.g. an emoji.
+    pub avatar: Option<String>,
+    /// Whether the player is a bot.
+    pub bot: bool,
+}
+
+impl Profile {
+    /// A profile for a human player called `name`.
+    pub fn new(name: &str) -> Self {
+        Profile { name: name.to_owned(), ..Default::default() }
+    }
+
+    /// A profile for a bot called `name`.
+    pub fn bot(name: &str) -> Self {
+        Profile { name: name.to_owned(), avatar: None, bot: true }
+    }
+
+    /// Sets the avatar.
+    pub fn with_avatar(mut self, avatar: &str) -> Self {
+        self.avatar = Some(avatar.to_owned());
+        self
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(avatar) = &self.avatar { write!(f,"{} ",avatar)?; }
+        write!(f,"{}",self.name)?;
+        if self.bot { write!(f," [bot]")?; }
+        Ok(())
+    }
+}
+
 /// A struct containing the information about a player in the game that is available to the other
 /// players in the game.
-#[derive(Serialize,Deserialize,Copy,Clone)]
+#[derive(Serialize,Deserialize,Clone)]
 pub struct PlayerInfo {
     /// Players unique ID.
     pub id: u64,
+    /// Player's name and how they'd like to be shown.
+    pub profile: Profile,
     /// Number of cards in player's hand.
     pub hand_len: usize,
+    /// Player's team, when playing in teams.
+    pub team: Option<usize>,
 }
 
 /// A struct containing the limited game state information available to players.
@@ -23,17 +66,24 @@ pub struct ToPlayState<'a> {
     /// Trump suit.
     pub trump: Suit,
 
-    /// Played attack cards.
-    pub attack_cards: Cow<'a,Vec<Card>>,
+    /// The face up card at the bottom of the talon that set the trump suit. Stays the same after
+    /// it has been drawn.
+    pub trump_card: Card,
+
+    /// Number of cards left in the talon.
+    pub draw_pile_len: usize,
 
-    /// Played defense cards.
-    pub defense_cards: Cow<'a,Vec<Card>>,
+    /// Number of cards that have been discarded.
+    pub discard_len: usize,
+
+    /// Attack cards played this round, each paired with its defense.
+    pub table: Cow<'a,Table>,
 
     /// Player's current hand.
     pub hand: Cow<'a,Vec<Card>>,
 
     /// All player's info, including this player.
-    pub player_info: Vec<PlayerInfo>,
+    pub player_info: Cow<'a,Vec<PlayerInfo>>,
 
     /// Index to `player_info` for whoever attacked last.
     pub last_attacker: usize,
@@ -44,14 +94,17 @@ pub struct ToPlayState<'a> {
     /// Index to `player_info` for player whose turn it currently is. Will be this player unless
     /// passed to [`DurakPlayer::observe_move()`].
     pub to_play: usize,
+
+    /// Whether moves can be taken back with [`Action::Undo`], see
+    /// [`DurakGame::with_takeback()`](crate::game::DurakGame::with_takeback).
+    pub takeback: bool,
 }
 
 // checks if defense beats attack
 fn beats_card(defense: &Card, attack: &Card, trump: &Suit) -> bool {
     if defense.suit == *trump {
-        if attack.suit == *trump {
-            if attack.rank >= defense.rank { return false; }
-        }
+        if attack.suit == *trump
+            && attack.rank >= defense.rank { return false; }
     } else {
         if attack.suit == *trump { return false; }
         if attack.suit != defense.suit { return false; }
@@ -72,6 +125,18 @@ pub enum ValidationError {
     InvalidAttack(Card),
     #[error("Invalid defense move: defense card {0} does not beat attack card {1}")]
     InvalidDefense(Card,Card),
+    #[error("Invalid defense move: {0} is not an unbeaten attack card")]
+    NotAnOpenAttack(Card),
+    #[error("Invalid attack move: no cards selected")]
+    EmptyAttack,
+    #[error("Invalid attack move: card {0} was selected more than once")]
+    DuplicateCard(Card),
+    #[error("Invalid attack move: all attack cards must be the same rank")]
+    MixedRanks,
+    #[error("Invalid attack move: only {0} more attack cards allowed this round")]
+    AttackLimit(usize),
+    #[error("Moves can't be taken back in this game")]
+    NoTakeback,
 }
 
 // validates moves
@@ -84,85 +149,132 @@ impl ToPlayState<'_> {
     pub fn to_static(&self) -> ToPlayState<'static> {
         ToPlayState {
             trump: self.trump,
-            attack_cards: Cow::Owned(self.attack_cards.clone().into_owned()),
-            defense_cards: Cow::Owned(self.defense_cards.clone().into_owned()),
+            trump_card: self.trump_card,
+            draw_pile_len: self.draw_pile_len,
+            discard_len: self.discard_len,
+            table: Cow::Owned(self.table.clone().into_owned()),
             hand: Cow::Owned(self.hand.clone().into_owned()),
-            player_info: self.player_info.clone(),
+            player_info: Cow::Owned(self.player_info.clone().into_owned()),
             last_attacker: self.last_attacker,
             defender: self.defender,
             to_play: self.to_play,
+            takeback: self.takeback,
         }
     }
 
+    /// Everyone but the player in `seat`, going round the table from the player on their left to
+    /// the player on their right.
+    pub fn others_from(&self, seat: usize) -> impl Iterator<Item=&PlayerInfo> {
+        let num_players = self.player_info.len();
+        (1..num_players).map(move |i| &self.player_info[(seat + i) % num_players])
+    }
+
+    /// Number of attack cards that can still be played this round. There can be at most six
+    /// attacks in a round, and never more unbeaten attacks than the defender has cards.
+    pub fn attack_limit(&self) -> usize {
+        let defender_cards = self.player_info[self.defender].hand_len.saturating_sub(self.table.open_attacks().count());
+        (6usize.saturating_sub(self.table.len())).min(defender_cards)
+    }
+
     /// Validates an attack move
     pub fn validate_attack(&self, action: &Action) -> Result<(),ValidationError> {
         if self.to_play == self.defender { return Err(ValidationError::WrongTurnType); }
         match action {
             Action::Play(attack_card) => {
-                if !self.hand.contains(&attack_card) {
-                    return Err(ValidationError::CardNotInHand(*attack_card));
-                }
-                if self.attack_cards.len() == 0 { return Ok(()); }
-                for card in self.attack_cards.iter() {
-                    if card.rank == attack_card.rank { return Ok(()); }
-                }
-                for card in self.defense_cards.iter() {
-                    if card.rank == attack_card.rank { return Ok(()); }
-                }
-                return Err(ValidationError::InvalidAttack(*attack_card));
+                return self.validate_attack_cards(std::slice::from_ref(attack_card));
+            },
+            Action::PlayMany(attack_cards) => {
+                return self.validate_attack_cards(attack_cards);
             },
-            Action::Pass => {
+            Action::Defend { .. } => {
+                return Err(ValidationError::WrongTurnType);
+            },
+            Action::Undo if !self.takeback => {
+                return Err(ValidationError::NoTakeback);
+            },
+            Action::Pass | Action::Forfeit | Action::Undo => {
             }
         }
         Ok(())
     }
 
+    // validates a set of same rank attack cards
+    fn validate_attack_cards(&self, attack_cards: &[Card]) -> Result<(),ValidationError> {
+        let Some(first) = attack_cards.first() else { return Err(ValidationError::EmptyAttack); };
+        for (i,attack_card) in attack_cards.iter().enumerate() {
+            if !self.hand.contains(attack_card) {
+                return Err(ValidationError::CardNotInHand(*attack_card));
+            }
+            if attack_cards[..i].contains(attack_card) {
+                return Err(ValidationError::DuplicateCard(*attack_card));
+            }
+            if attack_card.rank != first.rank {
+                return Err(ValidationError::MixedRanks);
+            }
+        }
+        if !self.table.is_empty() && !self.table.has_rank(first.rank) {
+            return Err(ValidationError::InvalidAttack(*first));
+        }
+        let limit = self.attack_limit();
+        if attack_cards.len() > limit {
+            return Err(ValidationError::AttackLimit(limit));
+        }
+        Ok(())
+    }
+
     /// Validates a defend move
     pub fn validate_defense(&self, action: &Action) -> Result<(), ValidationError> {
         if self.to_play != self.defender { return Err(ValidationError::WrongTurnType); }
         match action {
-            Action::Play(defense_card) => {
-                if !self.hand.contains(&defense_card) {
-                    return Err(ValidationError::CardNotInHand(*defense_card));
+            Action::Defend { card, against } => {
+                if !self.hand.contains(card) {
+                    return Err(ValidationError::CardNotInHand(*card));
+                }
+                if !self.table.open_attacks().any(|attack| attack == against) {
+                    return Err(ValidationError::NotAnOpenAttack(*against));
                 }
-                if let Some(attack_card) = self.attack_cards.last() {
-                    if !beats_card(defense_card,attack_card,&self.trump) { 
-                        return Err(ValidationError::InvalidDefense(*defense_card,*attack_card));
-                    }
-                } else {
-                    return Err(ValidationError::WrongTurnType);
+                if !beats_card(card,against,&self.trump) {
+                    return Err(ValidationError::InvalidDefense(*card,*against));
                 }
             },
-            Action::Pass => {
+            Action::Play(_) | Action::PlayMany(_) => {
+                return Err(ValidationError::WrongTurnType);
+            },
+            Action::Undo if !self.takeback => {
+                return Err(ValidationError::NoTakeback);
+            },
+            Action::Pass | Action::Forfeit | Action::Undo => {
             },
         }
         Ok(())
     }
 
+    /// Unbeaten attack cards that `card` could beat.
+    pub fn beatable_attacks(&self, card: &Card) -> Vec<Card> {
+        self.table.open_attacks().filter(|attack| beats_card(card,attack,&self.trump)).copied().collect()
+    }
+
     /// Validates a pile on
     pub fn validate_pile_on(&self, cards: &[Card]) -> Result<(), ValidationError> {
         if self.to_play == self.defender { return Err(ValidationError::WrongTurnType); }
-        for pile_on_card in cards {
+        if cards.len() > self.attack_limit() { return Err(ValidationError::AttackLimit(self.attack_limit())); }
+        for (i,pile_on_card) in cards.iter().enumerate() {
             self.validate_pile_on_single(pile_on_card)?;
+            if cards[..i].contains(pile_on_card) {
+                return Err(ValidationError::DuplicateCard(*pile_on_card));
+            }
         }
         Ok(())
     }
 
     /// Validates a single card for pile on. Does not validate turn type.
     pub fn validate_pile_on_single(&self, pile_on_card: &Card) -> Result<(), ValidationError> {
-        if !self.hand.contains(&pile_on_card) {
+        if !self.hand.contains(pile_on_card) {
             return Err(ValidationError::CardNotInHand(*pile_on_card));
         }
-        for card in self.attack_cards.iter() {
-            if card.rank == pile_on_card.rank {
-                return Ok(());
-            }
-        }
-        for card in self.defense_cards.iter() {
-            if card.rank == pile_on_card.rank {
-                return Ok(());
-            }
+        if self.table.has_rank(pile_on_card.rank) {
+            return Ok(());
         }
-        return Err(ValidationError::InvalidAttack(*pile_on_card));
+        Err(ValidationError::InvalidAttack(*pile_on_card))
     }
 }
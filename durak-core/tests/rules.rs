@@ -0,0 +1,443 @@
+//! Rules suite. Scripted players replay fixed games on seeded decks and check the state the engine
+//! shows them on each of their turns.
+
+use std::collections::VecDeque;
+use std::sync::{Arc,Mutex};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use durak_core::prelude::*;
+
+// parses a card written like "10C" or "QH"
+fn card(s: &str) -> Card {
+    let (rank,suit) = s.split_at(s.len() - 1);
+    let rank = match rank {
+        "6" => Rank::Six,
+        "7" => Rank::Seven,
+        "8" => Rank::Eight,
+        "9" => Rank::Nine,
+        "10" => Rank::Ten,
+        "J" => Rank::Jack,
+        "Q" => Rank::Queen,
+        "K" => Rank::King,
+        "A" => Rank::Ace,
+        _ => panic!("Bad rank in {}",s),
+    };
+    let suit = match suit {
+        "S" => Suit::Spades,
+        "D" => Suit::Diamonds,
+        "H" => Suit::Hearts,
+        "C" => Suit::Clubs,
+        _ => panic!("Bad suit in {}",s),
+    };
+    Card { rank, suit }
+}
+
+fn cards(s: &str) -> Vec<Card> {
+    s.split_whitespace().map(card).collect()
+}
+
+// hands are only sorted after a refill, so compare them as sets
+fn sorted(cards: &[Card]) -> Vec<usize> {
+    let mut inds: Vec<usize> = cards.iter().map(|&c| usize::try_from(c).unwrap()).collect();
+    inds.sort();
+    inds
+}
+
+#[derive(Debug)]
+enum Turn {
+    Attack(Action),
+    Defend(Action),
+    PileOn(Vec<Card>),
+}
+
+// one scripted turn, and what the player expects to be shown when it is asked for it
+struct Step {
+    turn: Turn,
+    hand: Option<Vec<Card>>,
+    table: Option<Vec<(Card,Option<Card>)>>,
+    defender: Option<usize>,
+    talon: Option<usize>,
+    trump_card: Option<Card>,
+}
+
+impl Step {
+    fn new(turn: Turn) -> Self {
+        Step { turn, hand: None, table: None, defender: None, talon: None, trump_card: None }
+    }
+
+    fn hand(mut self, hand: &str) -> Self {
+        self.hand = Some(cards(hand));
+        self
+    }
+
+    // each attack card, paired with the card that beat it
+    fn table(mut self, slots: &[(&str,Option<&str>)]) -> Self {
+        self.table = Some(slots.iter().map(|&(attack,defense)| (card(attack),defense.map(card))).collect());
+        self
+    }
+
+    fn defender(mut self, defender: usize) -> Self {
+        self.defender = Some(defender);
+        self
+    }
+
+    fn talon(mut self, talon: usize) -> Self {
+        self.talon = Some(talon);
+        self
+    }
+
+    fn trump_card(mut self, trump_card: &str) -> Self {
+        self.trump_card = Some(card(trump_card));
+        self
+    }
+}
+
+fn attack(attack: &str) -> Step {
+    Step::new(Turn::Attack(Action::attack_with(cards(attack))))
+}
+
+fn pass() -> Step {
+    Step::new(Turn::Attack(Action::Pass))
+}
+
+fn defend(defense: &str, against: &str) -> Step {
+    Step::new(Turn::Defend(Action::Defend { card: card(defense), against: card(against) }))
+}
+
+fn take() -> Step {
+    Step::new(Turn::Defend(Action::Pass))
+}
+
+fn pile_on(pile_on: &str) -> Step {
+    Step::new(Turn::PileOn(cards(pile_on)))
+}
+
+// replays a fixed list of turns, asserting on the state it is shown for each one
+struct ScriptedDurakPlayer {
+    case: &'static str,
+    id: u64,
+    script: Arc<Mutex<VecDeque<Step>>>,
+}
+
+impl ScriptedDurakPlayer {
+    fn next_turn(&self, kind: &str, state: &ToPlayState) -> Turn {
+        let step = self.script.lock().unwrap().pop_front()
+            .unwrap_or_else(|| panic!("{}: player {} was asked to {} but has nothing left to play",self.case,self.id,kind));
+        assert_eq!(state.player_info[state.to_play].id,self.id,"{}: player {} is not the one to play",self.case,self.id);
+        if let Some(hand) = step.hand {
+            assert_eq!(sorted(&state.hand),sorted(&hand),"{}: player {} was shown {} instead of {}",self.case,self.id,hand_fmt(&state.hand),hand_fmt(&hand));
+        }
+        if let Some(table) = step.table {
+            let shown: Vec<(Card,Option<Card>)> = state.table.slots().iter().map(|slot| (slot.attack,slot.defense)).collect();
+            assert_eq!(shown,table,"{}: player {} was shown the wrong table",self.case,self.id);
+        }
+        if let Some(defender) = step.defender {
+            assert_eq!(state.defender,defender,"{}: player {} was shown the wrong defender",self.case,self.id);
+        }
+        if let Some(talon) = step.talon {
+            assert_eq!(state.draw_pile_len,talon,"{}: player {} was shown the wrong talon size",self.case,self.id);
+        }
+        if let Some(trump_card) = step.trump_card {
+            assert_eq!(state.trump_card,trump_card,"{}: player {} was shown the wrong trump card",self.case,self.id);
+            assert_eq!(state.trump,trump_card.suit);
+        }
+        step.turn
+    }
+}
+
+#[async_trait]
+impl DurakPlayer for ScriptedDurakPlayer {
+    async fn attack(&mut self, state: &ToPlayState) -> Result<Action> {
+        match self.next_turn("attack",state) {
+            Turn::Attack(action) => Ok(action),
+            turn => panic!("{}: player {} was asked to attack but has {:?} scripted",self.case,self.id,turn),
+        }
+    }
+
+    async fn defend(&mut self, state: &ToPlayState) -> Result<Action> {
+        match self.next_turn("defend",state) {
+            Turn::Defend(action) => Ok(action),
+            turn => panic!("{}: player {} was asked to defend but has {:?} scripted",self.case,self.id,turn),
+        }
+    }
+
+    async fn pile_on(&mut self, state: &ToPlayState) -> Result<Vec<Card>> {
+        match self.next_turn("pile on",state) {
+            Turn::PileOn(cards) => Ok(cards),
+            turn => panic!("{}: player {} was asked to pile on but has {:?} scripted",self.case,self.id,turn),
+        }
+    }
+
+    async fn get_id(&mut self, _player_info: &[PlayerInfo]) -> Result<u64> {
+        Ok(self.id)
+    }
+}
+
+enum Expected {
+    // the game finished after `rounds` rounds, with `durak` or a draw
+    Finished { first_attacker: usize, rounds: usize, durak: Option<usize> },
+    // the engine rejected an invalid move with an error containing this text
+    Error(&'static str),
+}
+
+struct Case {
+    name: &'static str,
+    seed: u64,
+    // games are cut short after a few rounds, so a script only has to cover the rounds under test
+    round_limit: Option<usize>,
+    scripts: Vec<Vec<Step>>,
+    expected: Expected,
+}
+
+async fn run(case: Case) {
+    let mut game = DurakGame::new();
+    if let Some(limit) = case.round_limit {
+        game = game.with_round_limit(limit);
+    }
+    let mut scripts = Vec::new();
+    for (id,script) in case.scripts.into_iter().enumerate() {
+        let script = Arc::new(Mutex::new(VecDeque::from(script)));
+        game.add_player(Box::new(ScriptedDurakPlayer { case: case.name, id: id as u64, script: script.clone() })).await.unwrap();
+        scripts.push(script);
+    }
+    game.init(&mut StdRng::seed_from_u64(case.seed)).unwrap();
+
+    match (game.run_game().await,case.expected) {
+        (Ok(result),Expected::Finished { first_attacker, rounds, durak }) => {
+            let stats = result.stats();
+            assert_eq!(stats.first_attacker,first_attacker,"{}: wrong first attacker",case.name);
+            assert_eq!(stats.rounds,rounds,"{}: wrong number of rounds",case.name);
+            assert_eq!(stats.durak,durak,"{}: wrong durak",case.name);
+            assert_eq!(stats.draw,durak.is_none(),"{}: wrong draw",case.name);
+            for (id,script) in scripts.iter().enumerate() {
+                assert!(script.lock().unwrap().is_empty(),"{}: player {} has turns left over",case.name,id);
+            }
+        },
+        (Err(e),Expected::Error(msg)) => {
+            assert!(matches!(e,DurakError::InvalidMove { .. }),"{}: expected an invalid move, got \"{}\"",case.name,e);
+            assert!(e.to_string().contains(msg),"{}: expected an error containing \"{}\", got \"{}\"",case.name,msg,e);
+        },
+        (Ok(result),Expected::Error(msg)) => panic!("{}: expected an error containing \"{}\", game finished with {:?}",case.name,msg,result.stats()),
+        (Err(e),Expected::Finished { .. }) => panic!("{}: game failed with {}",case.name,e),
+    }
+}
+
+// Seed 1, two players: the ace of clubs is turned up, player 0 leads with the lowest trump (7C).
+//   player 0: 9C 7C KH QD 8C KS
+//   player 1: 8D KC JD 7D AS 6H
+//   talon: 9H 7H 6S AD KD 10S JC 8S AH QC 8H 6D 10H 9S JS 10C 6C 7S QS 9D JH 10D QH AC
+const TALON_1: &str = "9H 7H 6S AD KD 10S JC 8S AH QC 8H 6D 10H 9S JS 10C 6C 7S QS 9D JH 10D QH AC";
+
+// Seed 1, three players: the ace of clubs is turned up, player 2 leads with the lowest trump (7C)
+// against player 0.
+//   player 0: 9C KC QD AS 9H AD
+//   player 1: 8D KH 7D KS 7H KD
+//   player 2: 7C JD 8C 6H 6S 10S
+//   talon: JC 8S AH ...
+
+// Seed 1, six players: the whole deck is dealt so the last card dealt to player 5 (AC) sets the
+// trump, player 4 leads with the lowest trump (6C) against player 5.
+//   player 0: 9C QD 9H JC 10H QS
+//   player 1: 8D 7D 7H 8S 9S 9D
+//   player 2: 7C 8C 6S AH JS JH
+//   player 3: KC AS AD QC 10C 10D
+//   player 4: KH KS KD 8H 6C QH
+//   player 5: JD 6H 10S 6D 7S AC
+
+#[tokio::test]
+async fn dealing_and_trump() {
+    let cases = vec![
+        Case {
+            name: "two players are dealt six cards each from a shuffled deck",
+            seed: 1,
+            round_limit: Some(1),
+            scripts: vec![
+                vec![attack("KS").hand("9C 7C KH QD 8C KS").defender(1).talon(24).trump_card("AC"), pass()],
+                vec![defend("AS","KS").hand("8D KC JD 7D AS 6H").table(&[("KS",None)]).talon(24)],
+            ],
+            expected: Expected::Finished { first_attacker: 0, rounds: 1, durak: None },
+        },
+        Case {
+            name: "six players share the whole deck and the last card dealt sets the trump",
+            seed: 1,
+            round_limit: Some(1),
+            scripts: vec![
+                vec![pass().hand("9C QD 9H JC 10H QS"),pile_on("")],
+                vec![pass().hand("8D 7D 7H 8S 9S 9D"),pile_on("")],
+                vec![attack("AH").hand("7C 8C 6S AH JS JH").table(&[("KH",Some("AC"))]),pile_on("")],
+                vec![pile_on("").hand("KC AS AD QC 10C 10D")],
+                vec![
+                    attack("KH").hand("KH KS KD 8H 6C QH").defender(5).talon(0).trump_card("AC"),
+                    pass(),
+                    pile_on("KS KD").table(&[("KH",Some("AC")),("AH",None)]),
+                ],
+                vec![defend("AC","KH").hand("JD 6H 10S 6D 7S AC"),take()],
+            ],
+            expected: Expected::Finished { first_attacker: 4, rounds: 1, durak: None },
+        },
+    ];
+    for case in cases {
+        run(case).await;
+    }
+}
+
+#[tokio::test]
+async fn move_validation() {
+    let cases = vec![
+        Case {
+            name: "attack with a card from someone else's hand",
+            seed: 1,
+            round_limit: None,
+            scripts: vec![vec![attack("8D")],vec![]],
+            expected: Expected::Error("not in player's hand"),
+        },
+        Case {
+            name: "attack with cards of different ranks",
+            seed: 1,
+            round_limit: None,
+            scripts: vec![vec![attack("KS 9C")],vec![]],
+            expected: Expected::Error("same rank"),
+        },
+        Case {
+            name: "throw in a rank that is not on the table",
+            seed: 1,
+            round_limit: None,
+            scripts: vec![vec![attack("KS"),attack("9C")],vec![defend("AS","KS")]],
+            expected: Expected::Error("has not been played yet"),
+        },
+        Case {
+            name: "defend with a lower card of the attack suit",
+            seed: 1,
+            round_limit: None,
+            scripts: vec![vec![attack("KH")],vec![defend("6H","KH")]],
+            expected: Expected::Error("does not beat"),
+        },
+        Case {
+            name: "defend with a card of another suit",
+            seed: 1,
+            round_limit: None,
+            scripts: vec![vec![attack("KS")],vec![defend("8D","KS")]],
+            expected: Expected::Error("does not beat"),
+        },
+        Case {
+            name: "defend against a card that is not on the table",
+            seed: 1,
+            round_limit: None,
+            scripts: vec![vec![attack("KS")],vec![defend("AS","QS")]],
+            expected: Expected::Error("not an unbeaten attack card"),
+        },
+        Case {
+            name: "pile on a rank that is not on the table",
+            seed: 1,
+            round_limit: None,
+            scripts: vec![vec![attack("KS"),pile_on("9C")],vec![take()]],
+            expected: Expected::Error("has not been played yet"),
+        },
+        Case {
+            name: "pile on the same card twice",
+            seed: 1,
+            round_limit: None,
+            scripts: vec![vec![attack("KS"),pile_on("KH KH")],vec![take()]],
+            expected: Expected::Error("selected more than once"),
+        },
+        Case {
+            name: "any trump beats a card of another suit",
+            seed: 1,
+            round_limit: Some(1),
+            scripts: vec![vec![attack("KS"),pass()],vec![defend("KC","KS")]],
+            expected: Expected::Finished { first_attacker: 0, rounds: 1, durak: None },
+        },
+        Case {
+            name: "several cards of one rank are attacked with at once",
+            seed: 1,
+            round_limit: Some(1),
+            scripts: vec![
+                vec![attack("KH KS"),pass().table(&[("KH",Some("KC")),("KS",Some("AS"))])],
+                vec![defend("KC","KH").table(&[("KH",None),("KS",None)]),defend("AS","KS").table(&[("KH",Some("KC")),("KS",None)])],
+            ],
+            expected: Expected::Finished { first_attacker: 0, rounds: 1, durak: None },
+        },
+    ];
+    for case in cases {
+        run(case).await;
+    }
+}
+
+#[tokio::test]
+async fn refill_and_rotation() {
+    let cases = vec![
+        Case {
+            name: "after a defense the table is discarded, the attacker refills first and the defender leads",
+            seed: 1,
+            round_limit: Some(2),
+            scripts: vec![
+                vec![attack("KS"),pass(),defend("9H","6H").hand("9C 7C KH QD 8C 9H").talon(22)],
+                vec![defend("AS","KS"),attack("6H").hand("8D KC JD 7D 6H 7H").defender(0).talon(22),pass()],
+            ],
+            expected: Expected::Finished { first_attacker: 0, rounds: 2, durak: None },
+        },
+        Case {
+            name: "after a take the defender picks up the table and is skipped as the next leader",
+            seed: 1,
+            round_limit: Some(2),
+            scripts: vec![
+                vec![attack("KS"),pile_on("KH").table(&[("KS",None)]),attack("7H").hand("9C 7C QD 8C 9H 7H").defender(1).talon(22),pass()],
+                vec![take(),defend("KH","7H").hand("8D KC JD 7D AS 6H KS KH").talon(22)],
+            ],
+            expected: Expected::Finished { first_attacker: 0, rounds: 2, durak: None },
+        },
+        Case {
+            name: "with three players the defender leads against the next player after a defense",
+            seed: 1,
+            round_limit: Some(2),
+            scripts: vec![
+                vec![defend("AS","6S").defender(0),attack("9H").hand("9C KC QD 9H AD 8S").defender(1).talon(16),pass()],
+                vec![pass(),defend("KH","9H")],
+                vec![attack("6S").hand("7C JD 8C 6H 6S 10S").defender(0).talon(18),pass(),pass().table(&[("9H",Some("KH"))])],
+            ],
+            expected: Expected::Finished { first_attacker: 2, rounds: 2, durak: None },
+        },
+        Case {
+            name: "with three players the player after the defender leads after a take",
+            seed: 1,
+            round_limit: Some(2),
+            scripts: vec![
+                vec![take(),pass()],
+                vec![pile_on("").table(&[("6S",None),("6H",None)]),attack("7D").hand("8D KH 7D KS 7H KD").defender(2).talon(16),pass()],
+                vec![attack("6S"),pile_on("6H"),defend("JD","7D").hand("7C JD 8C 10S JC 8S")],
+            ],
+            expected: Expected::Finished { first_attacker: 2, rounds: 2, durak: None },
+        },
+    ];
+    for case in cases {
+        run(case).await;
+    }
+}
+
+#[tokio::test]
+async fn game_end() {
+    // player 0 attacks with one card a round and player 1 always takes: player 0 plays through
+    // their hand and then each card in the order it comes off the talon, until they are out
+    let order: Vec<String> = "9C 7C KH QD 8C KS".split_whitespace().chain(TALON_1.split_whitespace()).map(|s| s.to_owned()).collect();
+    let attacker = order.iter().enumerate().flat_map(|(round,c)| [attack(c).defender(1).talon(24usize.saturating_sub(round)),pile_on("")]).collect();
+    let defender = (0..order.len()).map(|_| take()).collect();
+    run(Case {
+        name: "the last player holding cards is the durak",
+        seed: 1,
+        round_limit: None,
+        scripts: vec![attacker,defender],
+        expected: Expected::Finished { first_attacker: 0, rounds: 30, durak: Some(1) },
+    }).await;
+
+    run(Case {
+        name: "the round limit ends the game in a draw",
+        seed: 1,
+        round_limit: Some(1),
+        scripts: vec![vec![pass()],vec![]],
+        expected: Expected::Finished { first_attacker: 0, rounds: 1, durak: None },
+    }).await;
+}
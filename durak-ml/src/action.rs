@@ -0,0 +1,112 @@
+//! Action encoding.
+//!
+//! Every decision is a choice out of [`ACTION_LEN`] actions: playing one of the 36 cards, or
+//! passing. A pile on is encoded as a sequence of single card choices ended by a pass. A defense
+//! only encodes the defending card; [`action_for()`] picks which attack it beats.
+//!
+//! There are no multi-card attacks in the action space. [`action_for()`] only ever plays a single
+//! card, so a policy leads with one card and throws in the rest of the rank on later turns.
+
+use durak_core::prelude::*;
+
+use crate::observation::{Decision,NUM_CARDS,card_index};
+
+/// Number of distinct actions.
+pub const ACTION_LEN: usize = NUM_CARDS + 1;
+
+/// Action index for passing (or finishing a pile on).
+pub const PASS: usize = NUM_CARDS;
+
+/// Encodes an action as an index in `0..ACTION_LEN`. A multi-card attack, e.g. recorded from a
+/// heuristic bot, is encoded by its first card as that is the closest move a policy can make.
+/// Forfeiting and taking back moves aren't in the action space and are encoded as a pass.
+pub fn encode_action(action: &Action) -> usize {
+    match action {
+        Action::Play(card) => card_index(card),
+        Action::PlayMany(cards) => cards.first().map(card_index).unwrap_or(PASS),
+        Action::Defend { card, .. } => card_index(card),
+        Action::Pass | Action::Forfeit | Action::Undo => PASS,
+    }
+}
+
+/// Decodes an action index. Returns `None` if the index is out of range.
+pub fn decode_action(index: usize) -> Option<Action> {
+    match index {
+        PASS => Some(Action::Pass),
+        i if i < NUM_CARDS => Card::try_from(i).ok().map(Action::Play),
+        _ => None,
+    }
+}
+
+/// Decodes an action index for a particular decision. A defending card is played against the
+/// strongest unbeaten attack it can beat, leaving weaker attacks for weaker cards.
+pub fn action_for(state: &ToPlayState, decision: Decision, index: usize) -> Option<Action> {
+    match (decision,decode_action(index)?) {
+        (Decision::Defend,Action::Play(card)) => {
+            let mut attacks = state.beatable_attacks(&card);
+            sort_cards(&mut attacks,state.trump);
+            attacks.last().map(|&against| Action::Defend { card, against })
+        },
+        (_,action) => Some(action),
+    }
+}
+
+/// Which actions are legal for `decision` in `state`.
+pub fn legal_action_mask(state: &ToPlayState, decision: Decision) -> Vec<bool> {
+    let mut mask = vec![false; ACTION_LEN];
+    for &card in state.hand.iter() {
+        mask[card_index(&card)] = match decision {
+            Decision::Attack => state.validate_attack(&Action::Play(card)).is_ok(),
+            Decision::Defend => !state.beatable_attacks(&card).is_empty(),
+            Decision::PileOn => state.attack_limit() > 0 && state.validate_pile_on_single(&card).is_ok(),
+        };
+    }
+    mask[PASS] = true;
+    mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observation::tests::{card,state};
+
+    #[test]
+    fn actions_round_trip() {
+        for index in 0..ACTION_LEN {
+            assert_eq!(decode_action(index).map(|action| encode_action(&action)),Some(index));
+        }
+        assert_eq!(decode_action(ACTION_LEN),None);
+        let sevens = vec![card(Rank::Seven,Suit::Clubs),card(Rank::Seven,Suit::Spades)];
+        assert_eq!(encode_action(&Action::PlayMany(sevens.clone())),card_index(&sevens[0]));
+    }
+
+    #[test]
+    fn attack_mask_follows_the_table() {
+        let hand = [card(Rank::Seven,Suit::Clubs),card(Rank::Seven,Suit::Spades),card(Rank::King,Suit::Hearts)];
+        let mask = legal_action_mask(&state(&hand,&[],false),Decision::Attack);
+        assert_eq!(mask.iter().filter(|&&legal| legal).count(),4);
+
+        let state = state(&hand,&[(card(Rank::Seven,Suit::Diamonds),Some(card(Rank::Nine,Suit::Diamonds)))],false);
+        let mask = legal_action_mask(&state,Decision::Attack);
+        assert!(mask[card_index(&hand[0])] && mask[card_index(&hand[1])] && mask[PASS]);
+        assert!(!mask[card_index(&hand[2])]);
+        // only ever single cards
+        for index in (0..ACTION_LEN).filter(|&i| mask[i]) {
+            assert!(matches!(action_for(&state,Decision::Attack,index),Some(Action::Play(_) | Action::Pass)));
+        }
+    }
+
+    #[test]
+    fn defense_beats_the_strongest_attack_it_can() {
+        let six = card(Rank::Six,Suit::Clubs);
+        let ten = card(Rank::Ten,Suit::Clubs);
+        let hand = [card(Rank::Jack,Suit::Clubs),card(Rank::Eight,Suit::Spades)];
+        let state = state(&hand,&[(six,None),(ten,None)],true);
+        let mask = legal_action_mask(&state,Decision::Defend);
+        assert!(mask[card_index(&hand[0])] && mask[PASS]);
+        assert!(!mask[card_index(&hand[1])]);
+        assert_eq!(action_for(&state,Decision::Defend,card_index(&hand[0])),Some(Action::Defend { card: hand[0], against: ten }));
+        assert_eq!(action_for(&state,Decision::Defend,card_index(&hand[1])),None);
+        assert_eq!(action_for(&state,Decision::Defend,PASS),Some(Action::Pass));
+    }
+}
@@ -0,0 +1,70 @@
+//! Serves a [`DurakEnv`] over stdin/stdout, one JSON object per line.
+//!
+//! Requests:
+//! - `{"cmd": "reset", "seed": 0}`
+//! - `{"cmd": "step", "action": 36}`
+//! - `{"cmd": "close"}`
+//!
+//! Every request except `close` gets one line back, either a step result
+//! `{"observation": [...], "reward": 0.0, "done": false, "info": {"action_mask": [...], "decision": "Attack", "error": null}}`
+//! or `{"error": "..."}`.
+
+use std::io::Write;
+
+use anyhow::{anyhow,bail,Result};
+use serde::Deserialize;
+use tokio::io::{AsyncBufReadExt,BufReader};
+
+use durak_ml::DurakEnv;
+
+const USAGE: &str = "usage: durak-env [--opponents heuristic,dummy,...]";
+
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "lowercase")]
+enum Request {
+    Reset { seed: u64 },
+    Step { action: usize },
+    Close,
+}
+
+async fn run_env() -> Result<()> {
+    let mut opponents = vec!["heuristic".to_owned()];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--opponents" => opponents = args.next()
+                .ok_or_else(|| anyhow!("Missing value for {}\n{}",arg,USAGE))?
+                .split(',').map(|s| s.trim().to_owned()).collect(),
+            _ => bail!("Unknown argument {}\n{}",arg,USAGE),
+        }
+    }
+
+    let mut env = DurakEnv::new(&opponents)?;
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = std::io::stdout();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() { continue; }
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(Request::Reset { seed }) => env.reset(seed).await,
+            Ok(Request::Step { action }) => env.step(action).await,
+            Ok(Request::Close) => break,
+            Err(e) => Err(e.into()),
+        };
+        let json = match response {
+            Ok(result) => serde_json::to_string(&result)?,
+            Err(e) => serde_json::json!({ "error": e.to_string() }).to_string(),
+        };
+        writeln!(stdout,"{}",json)?;
+        stdout.flush()?;
+    }
+    env.close();
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run_env().await {
+        eprintln!("ERROR: {}",e);
+        std::process::exit(1);
+    }
+}
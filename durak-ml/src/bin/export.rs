@@ -0,0 +1,37 @@
+use anyhow::{anyhow,bail,Result};
+
+use durak_ml::{self_play,export_npy};
+
+const USAGE: &str = "usage: durak-export [--games N] [--seed S] [--players dummy,heuristic,...] [--out DIR]";
+
+async fn run_export() -> Result<()> {
+    let mut games = 100;
+    let mut seed = 0;
+    let mut players = vec!["heuristic".to_owned(),"heuristic".to_owned()];
+    let mut out = "durak_data".to_owned();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| anyhow!("Missing value for {}\n{}",arg,USAGE));
+        match arg.as_str() {
+            "--games" => games = value()?.parse()?,
+            "--seed" => seed = value()?.parse()?,
+            "--players" => players = value()?.split(',').map(|s| s.trim().to_owned()).collect(),
+            "--out" => out = value()?,
+            _ => bail!("Unknown argument {}\n{}",arg,USAGE),
+        }
+    }
+
+    let records = self_play(&players,games,seed).await?;
+    export_npy(&out,&records)?;
+    eprintln!("Wrote {} records from {} games to {}",records.len(),games,out);
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run_export().await {
+        eprintln!("ERROR: {}",e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,349 @@
+//! A gym style environment for reinforcement learning.
+//!
+//! One seat is played by the caller through [`DurakEnv::step()`], every other seat is a built in
+//! bot. The game runs in its own task and the externally controlled seat is an ordinary
+//! [`DurakPlayer`] that hands each decision over a channel, so the env uses exactly the same rules
+//! as any other game.
+//!
+//! Actions use the [`action`](crate::action) encoding. A pile on is made one card per step and
+//! ended with a pass.
+
+use std::borrow::Cow;
+
+use anyhow::{anyhow,bail,Result};
+use async_trait::async_trait;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use serde::{Serialize,Deserialize};
+use tokio::sync::mpsc;
+use tracing::debug;
+
+use durak_core::game::BOT_ROUND_LIMIT;
+use durak_core::prelude::*;
+use durak_players::bot_by_name;
+
+use crate::action::{ACTION_LEN,action_for,legal_action_mask};
+use crate::observation::{Decision,ObservationTracker,OBS_LEN};
+
+/// Reward for getting out of the game.
+pub const WIN_REWARD: f32 = 1.0;
+
+/// Reward for being the durak.
+pub const LOSS_REWARD: f32 = -1.0;
+
+/// Reward for a game that ended without a durak.
+pub const DRAW_REWARD: f32 = 0.0;
+
+/// Extra information returned with every observation.
+#[derive(Serialize,Deserialize,Clone,Debug,Default)]
+pub struct StepInfo {
+    /// Which actions are legal for the current decision. All false once the game is over.
+    pub action_mask: Vec<bool>,
+    /// The kind of decision being asked for, `None` once the game is over.
+    pub decision: Option<Decision>,
+    /// Set if the last step was rejected, e.g. an illegal action.
+    pub error: Option<String>,
+}
+
+/// The result of [`DurakEnv::step()`].
+#[derive(Serialize,Deserialize,Clone,Debug)]
+pub struct StepResult {
+    /// Encoded observation, [`OBS_LEN`] long.
+    pub observation: Vec<f32>,
+    /// Reward for the step. Only non-zero when the game ends.
+    pub reward: f32,
+    /// Whether the game is over.
+    pub done: bool,
+    /// Action mask and other details.
+    pub info: StepInfo,
+}
+
+enum EnvEvent {
+    Decide {
+        observation: Vec<f32>,
+        mask: Vec<bool>,
+        decision: Decision,
+    },
+    Done(f32),
+    Error(String),
+}
+
+/// The externally controlled seat.
+struct ExternalPlayer {
+    tracker: ObservationTracker,
+    events: mpsc::UnboundedSender<EnvEvent>,
+    actions: mpsc::UnboundedReceiver<usize>,
+}
+
+impl ExternalPlayer {
+    async fn ask(&mut self, state: &ToPlayState<'_>, decision: Decision) -> Result<usize> {
+        self.events.send(EnvEvent::Decide {
+            observation: self.tracker.encode(state,decision),
+            mask: legal_action_mask(state,decision),
+            decision,
+        }).map_err(|_| anyhow!("Environment closed"))?;
+        self.actions.recv().await.ok_or_else(|| anyhow!("Environment closed"))
+    }
+
+    async fn ask_action(&mut self, state: &ToPlayState<'_>, decision: Decision) -> Result<Action> {
+        let index = self.ask(state,decision).await?;
+        action_for(state,decision,index).ok_or_else(|| anyhow!("Action {} is not valid here",index))
+    }
+}
+
+#[async_trait]
+impl DurakPlayer for ExternalPlayer {
+    async fn attack(&mut self, state: &ToPlayState) -> Result<Action> {
+        self.tracker.update(state);
+        self.ask_action(state,Decision::Attack).await
+    }
+
+    async fn defend(&mut self, state: &ToPlayState) -> Result<Action> {
+        self.tracker.update(state);
+        self.ask_action(state,Decision::Defend).await
+    }
+
+    async fn pile_on(&mut self, state: &ToPlayState) -> Result<Vec<Card>> {
+        self.tracker.update(state);
+        let mut partial = state.clone();
+        let mut cards = Vec::new();
+        loop {
+            match self.ask_action(&partial,Decision::PileOn).await? {
+                Action::Play(card) => {
+                    cards.push(card);
+                    partial.hand = Cow::Owned(partial.hand.iter().filter(|c| **c != card).copied().collect());
+                    partial.table.to_mut().add_attack(card);
+                },
+                _ => return Ok(cards),
+            }
+        }
+    }
+
+    async fn observe_move(&mut self, state: &ToPlayState, _events: &[MoveEvent]) -> Result<()> {
+        self.tracker.update(state);
+        Ok(())
+    }
+
+    async fn seated(&mut self, id: u64, _seat: usize, _rules: &Rules) -> Result<()> {
+        self.tracker.set_id(id);
+        Ok(())
+    }
+
+    async fn join(&mut self, _player_info: &[PlayerInfo]) -> Result<Profile> {
+        Ok(Profile::bot("Agent"))
+    }
+
+    async fn won(&mut self) -> Result<Ready> {
+        let _ = self.events.send(EnvEvent::Done(WIN_REWARD));
+        Ok(Ready::No)
+    }
+
+    async fn lost(&mut self) -> Result<Ready> {
+        let _ = self.events.send(EnvEvent::Done(LOSS_REWARD));
+        Ok(Ready::No)
+    }
+
+    async fn draw(&mut self) -> Result<Ready> {
+        let _ = self.events.send(EnvEvent::Done(DRAW_REWARD));
+        Ok(Ready::No)
+    }
+
+    async fn error(&mut self, error: &str) -> Result<()> {
+        let _ = self.events.send(EnvEvent::Error(error.to_owned()));
+        Ok(())
+    }
+}
+
+/// A single seat Durak environment. Seat 0 is controlled through [`DurakEnv::step()`], the
+/// remaining seats are played by bots.
+pub struct DurakEnv {
+    opponents: Vec<String>,
+    events: Option<mpsc::UnboundedReceiver<EnvEvent>>,
+    actions: Option<mpsc::UnboundedSender<usize>>,
+    game: Option<tokio::task::JoinHandle<()>>,
+    observation: Vec<f32>,
+    mask: Vec<bool>,
+    decision: Option<Decision>,
+}
+
+impl DurakEnv {
+    /// Creates an environment against the named built in bots (see
+    /// [`durak_players::BOT_NAMES`]).
+    pub fn new<S: AsRef<str>>(opponents: &[S]) -> Result<Self> {
+        if opponents.is_empty() || opponents.len() > 5 { bail!("Need between 1 and 5 opponents, got {}",opponents.len()); }
+        for name in opponents {
+            bot_by_name(name.as_ref()).ok_or_else(|| anyhow!("Unknown bot {}",name.as_ref()))?;
+        }
+        Ok(DurakEnv {
+            opponents: opponents.iter().map(|s| s.as_ref().to_owned()).collect(),
+            events: None,
+            actions: None,
+            game: None,
+            observation: vec![0.0; OBS_LEN],
+            mask: vec![false; ACTION_LEN],
+            decision: None,
+        })
+    }
+
+    /// Starts a new game dealt from `seed` and returns the first observation.
+    pub async fn reset(&mut self, seed: u64) -> Result<StepResult> {
+        self.close();
+        let (event_sender,event_receiver) = mpsc::unbounded_channel();
+        let (action_sender,action_receiver) = mpsc::unbounded_channel();
+
+        let mut game = DurakGame::new().with_round_limit(BOT_ROUND_LIMIT);
+        game.add_player(Box::new(ExternalPlayer {
+            tracker: ObservationTracker::new(),
+            events: event_sender,
+            actions: action_receiver,
+        })).await?;
+        for name in &self.opponents {
+            game.add_player(bot_by_name(name).ok_or_else(|| anyhow!("Unknown bot {}",name))?).await?;
+        }
+        game.init(&mut StdRng::seed_from_u64(seed))?;
+        self.game = Some(tokio::spawn(async move {
+            if let Err(e) = game.run_game().await {
+                debug!("Environment game ended with error: {}",e);
+            }
+        }));
+        self.events = Some(event_receiver);
+        self.actions = Some(action_sender);
+        self.next_event().await
+    }
+
+    /// Plays `action` for the controlled seat and runs the game until the seat has to decide again
+    /// or the game is over. An illegal action is rejected without changing the game.
+    pub async fn step(&mut self, action: usize) -> Result<StepResult> {
+        if self.decision.is_none() { bail!("Game is over, call reset first"); }
+        if action >= ACTION_LEN || !self.mask[action] {
+            return Ok(StepResult {
+                observation: self.observation.clone(),
+                reward: 0.0,
+                done: false,
+                info: StepInfo {
+                    action_mask: self.mask.clone(),
+                    decision: self.decision,
+                    error: Some(format!("Illegal action {}",action)),
+                },
+            });
+        }
+        self.actions.as_ref()
+            .ok_or_else(|| anyhow!("Game not started"))?
+            .send(action)
+            .map_err(|_| anyhow!("Game has stopped"))?;
+        self.next_event().await
+    }
+
+    /// The current action mask.
+    pub fn action_mask(&self) -> &[bool] {
+        &self.mask
+    }
+
+    /// Stops any game in progress.
+    pub fn close(&mut self) {
+        self.events = None;
+        self.actions = None;
+        if let Some(game) = self.game.take() {
+            game.abort();
+        }
+        self.decision = None;
+        self.mask = vec![false; ACTION_LEN];
+    }
+
+    async fn next_event(&mut self) -> Result<StepResult> {
+        let event = self.events.as_mut()
+            .ok_or_else(|| anyhow!("Game not started"))?
+            .recv().await;
+        match event {
+            Some(EnvEvent::Decide { observation, mask, decision }) => {
+                self.observation = observation;
+                self.mask = mask;
+                self.decision = Some(decision);
+                Ok(StepResult {
+                    observation: self.observation.clone(),
+                    reward: 0.0,
+                    done: false,
+                    info: StepInfo {
+                        action_mask: self.mask.clone(),
+                        decision: self.decision,
+                        error: None,
+                    },
+                })
+            },
+            Some(EnvEvent::Done(reward)) => {
+                self.close();
+                Ok(StepResult {
+                    observation: self.observation.clone(),
+                    reward,
+                    done: true,
+                    info: StepInfo {
+                        action_mask: self.mask.clone(),
+                        ..Default::default()
+                    },
+                })
+            },
+            Some(EnvEvent::Error(e)) => {
+                self.close();
+                bail!("Game error: {}",e)
+            },
+            None => {
+                self.close();
+                bail!("Game stopped unexpectedly")
+            },
+        }
+    }
+}
+
+impl Drop for DurakEnv {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opponents_are_checked() {
+        assert!(DurakEnv::new::<&str>(&[]).is_err());
+        assert!(DurakEnv::new(&["dummy"; 6]).is_err());
+        assert!(DurakEnv::new(&["nobody"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn plays_a_game_to_the_end() {
+        let mut env = DurakEnv::new(&["heuristic"]).unwrap();
+        assert!(env.step(0).await.is_err());
+        let mut step = env.reset(7).await.unwrap();
+        let mut steps = 0;
+        while !step.done {
+            assert_eq!(step.observation.len(),OBS_LEN);
+            assert_eq!(step.reward,0.0);
+            assert!(step.info.decision.is_some());
+            let legal = (0..ACTION_LEN).find(|&i| step.info.action_mask[i]).unwrap();
+            step = env.step(legal).await.unwrap();
+            steps += 1;
+            assert!(steps < 10_000);
+        }
+        assert!([WIN_REWARD,LOSS_REWARD,DRAW_REWARD].contains(&step.reward));
+        assert!(step.info.decision.is_none());
+        assert!(env.step(0).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn illegal_actions_are_rejected() {
+        let mut env = DurakEnv::new(&["dummy","dummy"]).unwrap();
+        let first = env.reset(3).await.unwrap();
+        let illegal = (0..ACTION_LEN).find(|&i| !first.info.action_mask[i]).unwrap();
+        for action in [illegal,ACTION_LEN] {
+            let step = env.step(action).await.unwrap();
+            assert!(!step.done && step.info.error.is_some());
+            assert_eq!(step.observation,first.observation);
+            assert_eq!(step.info.action_mask,first.info.action_mask);
+        }
+        // the game carries on as if nothing happened
+        let legal = (0..ACTION_LEN).find(|&i| first.info.action_mask[i]).unwrap();
+        assert!(env.step(legal).await.unwrap().info.error.is_none());
+    }
+}
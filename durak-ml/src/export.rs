@@ -0,0 +1,286 @@
+//! Training data export.
+//!
+//! [`RecordingPlayer`] wraps any player and records an `(observation, action, outcome)` triple
+//! for every decision it makes. Records are written out as NPY files that can be loaded directly
+//! with `numpy.load`.
+
+use std::borrow::Cow;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc,Mutex};
+
+use anyhow::{anyhow,Result};
+use async_trait::async_trait;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use durak_core::game::BOT_ROUND_LIMIT;
+use durak_core::prelude::*;
+use durak_players::bot_by_name;
+
+use crate::action::{encode_action,PASS};
+use crate::observation::{Decision,ObservationTracker,OBS_LEN};
+
+/// A single training example.
+#[derive(Clone,Debug)]
+pub struct Record {
+    /// Encoded observation, [`OBS_LEN`] long.
+    pub observation: Vec<f32>,
+    /// Encoded action taken.
+    pub action: usize,
+    /// Final outcome for the player that made the decision: 1 if they got out, -1 if they were
+    /// the durak and 0 if the game was a draw.
+    pub outcome: f32,
+}
+
+/// Shared storage that [`RecordingPlayer`]s append finished games to.
+pub type RecordSink = Arc<Mutex<Vec<Record>>>;
+
+/// Wraps a player and records every decision it makes.
+/// Records are only added to the sink once the game is over and the outcome is known.
+pub struct RecordingPlayer {
+    engine: Box<dyn DurakPlayer>,
+    tracker: ObservationTracker,
+    pending: Vec<Record>,
+    sink: RecordSink,
+}
+
+impl RecordingPlayer {
+    /// Wraps `engine`, sending finished records to `sink`.
+    pub fn new(engine: Box<dyn DurakPlayer>, sink: RecordSink) -> Self {
+        RecordingPlayer {
+            engine,
+            tracker: ObservationTracker::new(),
+            pending: Vec::new(),
+            sink,
+        }
+    }
+
+    fn record(&mut self, state: &ToPlayState, decision: Decision, action: usize) {
+        self.pending.push(Record {
+            observation: self.tracker.encode(state,decision),
+            action,
+            outcome: 0.0,
+        });
+    }
+
+    fn finish(&mut self, outcome: f32) {
+        for record in self.pending.iter_mut() {
+            record.outcome = outcome;
+        }
+        if let Ok(mut sink) = self.sink.lock() {
+            sink.append(&mut self.pending);
+        }
+        self.pending.clear();
+        self.tracker.reset();
+    }
+}
+
+#[async_trait]
+impl DurakPlayer for RecordingPlayer {
+    async fn attack(&mut self, state: &ToPlayState) -> Result<Action> {
+        self.tracker.update(state);
+        let action = self.engine.attack(state).await?;
+        self.record(state,Decision::Attack,encode_action(&action));
+        Ok(action)
+    }
+
+    async fn defend(&mut self, state: &ToPlayState) -> Result<Action> {
+        self.tracker.update(state);
+        let action = self.engine.defend(state).await?;
+        self.record(state,Decision::Defend,encode_action(&action));
+        Ok(action)
+    }
+
+    async fn pile_on(&mut self, state: &ToPlayState) -> Result<Vec<Card>> {
+        self.tracker.update(state);
+        let cards = self.engine.pile_on(state).await?;
+        // one record per card, each seeing the cards already chosen moved from the hand to the table
+        let mut partial = state.clone();
+        for card in cards.iter() {
+            self.record(&partial,Decision::PileOn,encode_action(&Action::Play(*card)));
+            partial.hand = Cow::Owned(partial.hand.iter().filter(|c| *c != card).copied().collect());
+            partial.table.to_mut().add_attack(*card);
+        }
+        self.record(&partial,Decision::PileOn,PASS);
+        Ok(cards)
+    }
+
+    async fn exchange_trump(&mut self, state: &ToPlayState) -> Result<bool> {
+        self.engine.exchange_trump(state).await
+    }
+
+    async fn observe_move(&mut self, state: &ToPlayState, events: &[MoveEvent]) -> Result<()> {
+        self.tracker.update(state);
+        self.engine.observe_move(state,events).await
+    }
+
+    fn is_remote(&self) -> bool {
+        self.engine.is_remote()
+    }
+
+    async fn seated(&mut self, id: u64, seat: usize, rules: &Rules) -> Result<()> {
+        self.tracker.set_id(id);
+        self.engine.seated(id,seat,rules).await
+    }
+
+    async fn join(&mut self, player_info: &[PlayerInfo]) -> Result<Profile> {
+        self.engine.join(player_info).await
+    }
+
+    async fn lost(&mut self) -> Result<Ready> {
+        self.finish(-1.0);
+        self.engine.lost().await
+    }
+
+    async fn won(&mut self) -> Result<Ready> {
+        self.finish(1.0);
+        self.engine.won().await
+    }
+
+    async fn draw(&mut self) -> Result<Ready> {
+        self.finish(0.0);
+        self.engine.draw().await
+    }
+
+    async fn message(&mut self, msg: &str) -> Result<()> {
+        self.engine.message(msg).await
+    }
+
+    async fn error(&mut self, error: &str) -> Result<()> {
+        // the outcome is unknown so these records are useless
+        self.pending.clear();
+        self.tracker.reset();
+        self.engine.error(error).await
+    }
+}
+
+/// Plays `games` games between the named bots and returns the recorded decisions of every seat.
+/// Game `i` is dealt from seed `seed + i`.
+pub async fn self_play(bots: &[String], games: usize, seed: u64) -> Result<Vec<Record>> {
+    let sink = RecordSink::default();
+    for game_ind in 0..games {
+        let mut game = DurakGame::new().with_round_limit(BOT_ROUND_LIMIT);
+        for name in bots {
+            let bot = bot_by_name(name).ok_or_else(|| anyhow!("Unknown bot {}",name))?;
+            game.add_player(Box::new(RecordingPlayer::new(bot,sink.clone()))).await?;
+        }
+        game.init(&mut StdRng::seed_from_u64(seed.wrapping_add(game_ind as u64)))?;
+        game.run_game().await?;
+    }
+    let records = std::mem::take(&mut *sink.lock().map_err(|e| anyhow!("Lock error: {}",e))?);
+    Ok(records)
+}
+
+fn write_npy<W: Write>(writer: &mut W, descr: &str, shape: &[usize], data: &[u8]) -> Result<()> {
+    let shape_str = match shape {
+        [n] => format!("({},)",n),
+        _ => format!("({})",shape.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(", ")),
+    };
+    let mut header = format!("{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",descr,shape_str);
+    // magic (6) + version (2) + header length (2) + header must be a multiple of 64
+    let unpadded = 10 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+    header.push('\n');
+    writer.write_all(b"\x93NUMPY\x01\x00")?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    writer.write_all(data)?;
+    Ok(())
+}
+
+/// Writes a little endian `f32` array in NPY format.
+pub fn write_npy_f32<W: Write>(writer: &mut W, shape: &[usize], data: &[f32]) -> Result<()> {
+    let bytes: Vec<u8> = data.iter().flat_map(|x| x.to_le_bytes()).collect();
+    write_npy(writer,"<f4",shape,&bytes)
+}
+
+/// Writes a little endian `i64` array in NPY format.
+pub fn write_npy_i64<W: Write>(writer: &mut W, shape: &[usize], data: &[i64]) -> Result<()> {
+    let bytes: Vec<u8> = data.iter().flat_map(|x| x.to_le_bytes()).collect();
+    write_npy(writer,"<i8",shape,&bytes)
+}
+
+/// Writes `observations.npy` (N x [`OBS_LEN`] `f32`), `actions.npy` (N `i64`) and
+/// `outcomes.npy` (N `f32`) to `dir`, creating it if needed.
+pub fn export_npy<P: AsRef<Path>>(dir: P, records: &[Record]) -> Result<()> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir)?;
+    let n = records.len();
+
+    let observations: Vec<f32> = records.iter().flat_map(|r| r.observation.iter().copied()).collect();
+    let mut file = std::io::BufWriter::new(std::fs::File::create(dir.join("observations.npy"))?);
+    write_npy_f32(&mut file,&[n,OBS_LEN],&observations)?;
+    file.flush()?;
+
+    let actions: Vec<i64> = records.iter().map(|r| r.action as i64).collect();
+    let mut file = std::io::BufWriter::new(std::fs::File::create(dir.join("actions.npy"))?);
+    write_npy_i64(&mut file,&[n],&actions)?;
+    file.flush()?;
+
+    let outcomes: Vec<f32> = records.iter().map(|r| r.outcome).collect();
+    let mut file = std::io::BufWriter::new(std::fs::File::create(dir.join("outcomes.npy"))?);
+    write_npy_f32(&mut file,&[n],&outcomes)?;
+    file.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // splits an NPY file into its header dict and data, checking the preamble and padding
+    fn parse_npy(bytes: &[u8]) -> (String,&[u8]) {
+        assert_eq!(&bytes[..8],b"\x93NUMPY\x01\x00");
+        let header_len = u16::from_le_bytes([bytes[8],bytes[9]]) as usize;
+        assert_eq!((10 + header_len) % 64,0);
+        let header = std::str::from_utf8(&bytes[10..10 + header_len]).unwrap();
+        assert!(header.ends_with('\n'));
+        (header.trim_end().to_owned(),&bytes[10 + header_len..])
+    }
+
+    #[test]
+    fn npy_f32_matrix() {
+        let mut bytes = Vec::new();
+        write_npy_f32(&mut bytes,&[2,3],&[0.0,1.0,2.0,3.0,4.0,-1.5]).unwrap();
+        let (header,data) = parse_npy(&bytes);
+        assert_eq!(header,"{'descr': '<f4', 'fortran_order': False, 'shape': (2, 3), }");
+        let values: Vec<f32> = data.chunks_exact(4).map(|b| f32::from_le_bytes([b[0],b[1],b[2],b[3]])).collect();
+        assert_eq!(values,vec![0.0,1.0,2.0,3.0,4.0,-1.5]);
+    }
+
+    #[test]
+    fn npy_i64_vector() {
+        let mut bytes = Vec::new();
+        write_npy_i64(&mut bytes,&[3],&[7,-1,36]).unwrap();
+        let (header,data) = parse_npy(&bytes);
+        // one dimensional shapes need the trailing comma to be a tuple
+        assert_eq!(header,"{'descr': '<i8', 'fortran_order': False, 'shape': (3,), }");
+        assert_eq!(data.len(),24);
+        assert_eq!(i64::from_le_bytes(data[8..16].try_into().unwrap()),-1);
+    }
+
+    #[test]
+    fn export_writes_all_arrays() {
+        let dir = std::env::temp_dir().join(format!("durak-npy-{}",std::process::id()));
+        let records = vec![
+            Record { observation: vec![0.5; OBS_LEN], action: 3, outcome: 1.0 },
+            Record { observation: vec![0.0; OBS_LEN], action: PASS, outcome: -1.0 },
+        ];
+        export_npy(&dir,&records).unwrap();
+        let observations = std::fs::read(dir.join("observations.npy")).unwrap();
+        let actions = std::fs::read(dir.join("actions.npy")).unwrap();
+        let outcomes = std::fs::read(dir.join("outcomes.npy")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let (header,data) = parse_npy(&observations);
+        assert!(header.contains(&format!("'shape': (2, {})",OBS_LEN)));
+        assert_eq!(data.len(),2 * OBS_LEN * 4);
+        let (_,data) = parse_npy(&actions);
+        assert_eq!(i64::from_le_bytes(data[8..16].try_into().unwrap()),PASS as i64);
+        let (header,data) = parse_npy(&outcomes);
+        assert!(header.contains("'shape': (2,)"));
+        assert_eq!(f32::from_le_bytes(data[4..8].try_into().unwrap()),-1.0);
+    }
+}
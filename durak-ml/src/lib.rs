@@ -0,0 +1,25 @@
+//!
+//! Machine learning support for [Durak]. This crate turns the limited game state a player sees
+//! ([`ToPlayState`]) into fixed length tensors, encodes actions as indices, and exports recorded
+//! games as training data. Training itself happens elsewhere; this crate keeps the encoding next to
+//! the engine so it stays in sync with the rules. Trained weights can be played with
+//! [`NeuralDurakPlayer`], and [`DurakEnv`] lets an external training script play one seat step by
+//! step (see the `durak-env` binary for a stdin/stdout JSON front end).
+//!
+//! [Durak]: https://en.wikipedia.org/wiki/Durak
+//! [`ToPlayState`]: durak_core::toplaystate::ToPlayState
+//!
+
+#![warn(missing_docs)]
+
+pub mod observation;
+pub mod action;
+pub mod export;
+pub mod policy;
+pub mod env;
+
+pub use observation::{Decision,ObservationTracker,OBS_LEN};
+pub use action::{ACTION_LEN,encode_action,decode_action,action_for,legal_action_mask};
+pub use export::{Record,RecordingPlayer,self_play,export_npy};
+pub use policy::{Mlp,NeuralDurakPlayer};
+pub use env::{DurakEnv,StepResult,StepInfo};
@@ -0,0 +1,217 @@
+//! Fixed length observation encoding.
+//!
+//! [`ToPlayState`] only shows the current table, so [`ObservationTracker`] follows the game from
+//! one state to the next to keep track of the discard pile and any cards known to be in an
+//! opponent's hand (i.e. cards they picked up).
+//!
+//! Layout of the encoded observation (all `f32`):
+//!
+//! | offset | length | contents |
+//! |--------|--------|----------|
+//! | 0      | 36     | own hand |
+//! | 36     | 36     | attack cards on the table |
+//! | 72     | 36     | defense cards on the table |
+//! | 108    | 36     | discard pile |
+//! | 144    | 36     | cards known to be held by an opponent |
+//! | 180    | 4      | trump suit, one-hot |
+//! | 184    | 6      | hand sizes / 36, starting with own seat and going clock-wise |
+//! | 190    | 6      | defender seat relative to own seat, one-hot |
+//! | 196    | 3      | decision type, one-hot ([`Decision`]) |
+//! | 199    | 1      | cards left in the talon / 36 |
+//!
+//! Cards are indexed with `usize::try_from(card)`.
+
+use std::collections::HashMap;
+
+use serde::{Serialize,Deserialize};
+
+use durak_core::prelude::*;
+
+/// Number of cards in the deck.
+pub const NUM_CARDS: usize = 36;
+
+/// Maximum number of players at a table.
+pub const MAX_PLAYERS: usize = 6;
+
+/// Length of an encoded observation.
+pub const OBS_LEN: usize = 5 * NUM_CARDS + 4 + 2 * MAX_PLAYERS + 3 + 1;
+
+const HAND: usize = 0;
+const ATTACK: usize = HAND + NUM_CARDS;
+const DEFENSE: usize = ATTACK + NUM_CARDS;
+const DISCARD: usize = DEFENSE + NUM_CARDS;
+const KNOWN: usize = DISCARD + NUM_CARDS;
+const TRUMP: usize = KNOWN + NUM_CARDS;
+const HAND_SIZES: usize = TRUMP + 4;
+const DEFENDER: usize = HAND_SIZES + MAX_PLAYERS;
+const DECISION: usize = DEFENDER + MAX_PLAYERS;
+const TALON: usize = DECISION + 3;
+
+/// The kind of decision a player is being asked to make.
+#[derive(Copy,Clone,PartialEq,Debug,Serialize,Deserialize)]
+#[allow(missing_docs)]
+pub enum Decision {
+    Attack = 0,
+    Defend = 1,
+    PileOn = 2,
+}
+
+pub(crate) fn card_index(card: &Card) -> usize {
+    usize::try_from(*card).unwrap()
+}
+
+/// Follows successive [`ToPlayState`]s for one player and remembers what has left the table.
+#[derive(Clone,Default)]
+pub struct ObservationTracker {
+    id: Option<u64>,
+    table: Table,
+    defender_id: u64,
+    discards: Vec<Card>,
+    known: HashMap<u64,Vec<Card>>,
+}
+
+impl ObservationTracker {
+    /// Creates a tracker for a player whose ID is not known yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the ID of the player this tracker is following.
+    pub fn set_id(&mut self, id: u64) {
+        self.id = Some(id);
+    }
+
+    /// Clears all history, e.g. for a new game.
+    pub fn reset(&mut self) {
+        *self = ObservationTracker {
+            id: self.id,
+            ..Self::default()
+        };
+    }
+
+    /// Discarded cards seen so far.
+    pub fn discards(&self) -> &[Card] {
+        &self.discards
+    }
+
+    /// Cards known to be in the hand of the player with ID `id`.
+    pub fn known_cards(&self, id: u64) -> &[Card] {
+        self.known.get(&id).map(|cards| cards.as_slice()).unwrap_or(&[])
+    }
+
+    /// Updates the history with the latest state. Call this with every state the player is shown,
+    /// both on its own turns and in [`DurakPlayer::observe_move()`].
+    pub fn update(&mut self, state: &ToPlayState) {
+        let table_cleared = state.table.is_empty() && !self.table.is_empty();
+        if table_cleared {
+            let table: Vec<Card> = self.table.cards().copied().collect();
+            if self.table.all_beaten() {
+                self.discards.extend(table);
+            } else if Some(self.defender_id) != self.id {
+                self.known.entry(self.defender_id).or_default().extend(table);
+            }
+        }
+        // anything on the table is no longer in anyone's hand
+        for cards in self.known.values_mut() {
+            cards.retain(|card| !state.table.contains(card));
+        }
+        self.table = state.table.clone().into_owned();
+        self.defender_id = state.player_info[state.defender].id;
+    }
+
+    /// Encodes the state from the point of view of the player at `to_play` making `decision`.
+    pub fn encode(&self, state: &ToPlayState, decision: Decision) -> Vec<f32> {
+        let mut obs = vec![0f32; OBS_LEN];
+        for card in state.hand.iter() { obs[HAND + card_index(card)] = 1.0; }
+        for card in state.table.attacks() { obs[ATTACK + card_index(card)] = 1.0; }
+        for card in state.table.defenses() { obs[DEFENSE + card_index(card)] = 1.0; }
+        for card in self.discards.iter() { obs[DISCARD + card_index(card)] = 1.0; }
+
+        let num_players = state.player_info.len();
+        let own_seat = self.id
+            .and_then(|id| state.player_info.iter().position(|info| info.id == id))
+            .unwrap_or(state.to_play);
+        for info in state.player_info.iter().filter(|info| Some(info.id) != self.id) {
+            for card in self.known_cards(info.id) { obs[KNOWN + card_index(card)] = 1.0; }
+        }
+
+        obs[TRUMP + state.trump as usize] = 1.0;
+        for offset in 0..num_players.min(MAX_PLAYERS) {
+            let info = &state.player_info[(own_seat + offset) % num_players];
+            obs[HAND_SIZES + offset] = info.hand_len as f32 / NUM_CARDS as f32;
+        }
+        obs[DEFENDER + (state.defender + num_players - own_seat) % num_players] = 1.0;
+        obs[DECISION + decision as usize] = 1.0;
+        obs[TALON] = state.draw_pile_len as f32 / NUM_CARDS as f32;
+        obs
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::borrow::Cow;
+
+    pub(crate) fn card(rank: Rank, suit: Suit) -> Card {
+        Card { rank, suit }
+    }
+
+    // a two player state where seat 0 holds `hand` and `table` has been played
+    pub(crate) fn state(hand: &[Card], table: &[(Card,Option<Card>)], defending: bool) -> ToPlayState<'static> {
+        let mut cards = Table::new();
+        for &(attack,defense) in table {
+            cards.add_attack(attack);
+            if let Some(defense) = defense { cards.add_defense(defense,attack); }
+        }
+        let info = |id,hand_len| PlayerInfo { id, profile: Profile::default(), hand_len, team: None };
+        ToPlayState {
+            trump: Suit::Hearts,
+            trump_card: card(Rank::Ace,Suit::Hearts),
+            draw_pile_len: 10,
+            discard_len: 0,
+            table: Cow::Owned(cards),
+            hand: Cow::Owned(hand.to_vec()),
+            player_info: Cow::Owned(vec![info(0,hand.len()),info(1,6)]),
+            last_attacker: if defending { 1 } else { 0 },
+            defender: if defending { 0 } else { 1 },
+            to_play: 0,
+            takeback: false,
+        }
+    }
+
+    #[test]
+    fn beaten_cards_are_discarded() {
+        let seven = card(Rank::Seven,Suit::Clubs);
+        let nine = card(Rank::Nine,Suit::Clubs);
+        let mut tracker = ObservationTracker::new();
+        tracker.set_id(0);
+        tracker.update(&state(&[],&[(seven,Some(nine))],false));
+        tracker.update(&state(&[],&[],false));
+        assert_eq!(tracker.discards(),&[seven,nine]);
+        assert!(tracker.known_cards(1).is_empty());
+    }
+
+    #[test]
+    fn taken_cards_are_known_until_played() {
+        let seven = card(Rank::Seven,Suit::Clubs);
+        let eight = card(Rank::Eight,Suit::Spades);
+        let mut tracker = ObservationTracker::new();
+        tracker.set_id(0);
+        tracker.update(&state(&[],&[(seven,None),(eight,None)],false));
+        tracker.update(&state(&[],&[],false));
+        assert_eq!(tracker.known_cards(1),&[seven,eight]);
+        tracker.update(&state(&[],&[(eight,None)],true));
+        assert_eq!(tracker.known_cards(1),&[seven]);
+
+        let obs = tracker.encode(&state(&[eight],&[],false),Decision::Attack);
+        assert_eq!(obs.len(),OBS_LEN);
+        assert_eq!(obs[HAND + card_index(&eight)],1.0);
+        assert_eq!(obs[KNOWN + card_index(&seven)],1.0);
+        assert_eq!(obs[DECISION + Decision::Attack as usize],1.0);
+        // the defender sits one to the left
+        assert_eq!(obs[DEFENDER + 1],1.0);
+
+        tracker.reset();
+        assert!(tracker.known_cards(1).is_empty());
+    }
+}
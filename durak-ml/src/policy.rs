@@ -0,0 +1,325 @@
+//! A small CPU only MLP policy and a player that uses it.
+//!
+//! Weights are stored in a flat little endian file:
+//!
+//! ```text
+//! magic        8 bytes   b"DURAKMLP"
+//! num_layers   u32
+//! per layer:
+//!   inputs     u32
+//!   outputs    u32
+//!   weights    f32 * outputs * inputs   (row major, one row per output)
+//!   biases     f32 * outputs
+//! ```
+//!
+//! The first layer takes [`OBS_LEN`] inputs and the last layer gives [`ACTION_LEN`] scores.
+//! Hidden layers use ReLU, the output layer is linear. A file can be written from numpy with
+//! `f.write(b"DURAKMLP"); f.write(np.uint32(n).tobytes())` followed by
+//! `np.uint32([w.shape[1], w.shape[0]]).tobytes() + w.astype("<f4").tobytes() + b.astype("<f4").tobytes()`
+//! for each layer.
+
+use std::io::{Read,Write};
+use std::path::Path;
+
+use anyhow::{bail,Result};
+use async_trait::async_trait;
+use rand::{Rng,SeedableRng};
+use rand::rngs::StdRng;
+
+use durak_core::prelude::*;
+use durak_players::HeuristicDurakPlayer;
+
+use crate::action::{ACTION_LEN,PASS,action_for,decode_action,legal_action_mask};
+use crate::observation::{Decision,ObservationTracker,OBS_LEN};
+
+const MAGIC: &[u8; 8] = b"DURAKMLP";
+
+/// A fully connected layer.
+#[derive(Clone,Debug)]
+pub struct Layer {
+    inputs: usize,
+    outputs: usize,
+    weights: Vec<f32>,
+    biases: Vec<f32>,
+}
+
+impl Layer {
+    /// Creates a layer from row major weights (`outputs` rows of `inputs`) and biases.
+    pub fn new(inputs: usize, outputs: usize, weights: Vec<f32>, biases: Vec<f32>) -> Result<Self> {
+        if weights.len() != inputs * outputs || biases.len() != outputs {
+            bail!("Layer shape mismatch: expected {}x{} weights and {} biases",outputs,inputs,outputs);
+        }
+        Ok(Layer { inputs, outputs, weights, biases })
+    }
+
+    fn forward(&self, input: &[f32]) -> Vec<f32> {
+        self.weights
+            .chunks(self.inputs)
+            .zip(self.biases.iter())
+            .map(|(row,bias)| row.iter().zip(input).map(|(w,x)| w * x).sum::<f32>() + bias)
+            .collect()
+    }
+}
+
+/// A multi-layer perceptron mapping an observation to a score for every action.
+#[derive(Clone,Debug)]
+pub struct Mlp {
+    layers: Vec<Layer>,
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<usize> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf) as usize)
+}
+
+fn read_f32s<R: Read>(reader: &mut R, n: usize) -> Result<Vec<f32>> {
+    let mut buf = vec![0u8; n * 4];
+    reader.read_exact(&mut buf)?;
+    Ok(buf.chunks_exact(4).map(|b| f32::from_le_bytes([b[0],b[1],b[2],b[3]])).collect())
+}
+
+impl Mlp {
+    /// Creates a network from its layers, checking that the shapes line up.
+    pub fn new(layers: Vec<Layer>) -> Result<Self> {
+        match (layers.first(),layers.last()) {
+            (Some(first),Some(last)) => {
+                if first.inputs != OBS_LEN { bail!("First layer takes {} inputs, expected {}",first.inputs,OBS_LEN); }
+                if last.outputs != ACTION_LEN { bail!("Last layer gives {} outputs, expected {}",last.outputs,ACTION_LEN); }
+            },
+            _ => bail!("Network has no layers"),
+        }
+        for pair in layers.windows(2) {
+            if pair[0].outputs != pair[1].inputs { bail!("Layer output {} does not match next layer input {}",pair[0].outputs,pair[1].inputs); }
+        }
+        Ok(Mlp { layers })
+    }
+
+    /// Reads a network in the flat format described in the module docs.
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC { bail!("Not a durak MLP weights file"); }
+        let num_layers = read_u32(reader)?;
+        let mut layers = Vec::with_capacity(num_layers);
+        for _ in 0..num_layers {
+            let inputs = read_u32(reader)?;
+            let outputs = read_u32(reader)?;
+            let weights = read_f32s(reader,inputs * outputs)?;
+            let biases = read_f32s(reader,outputs)?;
+            layers.push(Layer::new(inputs,outputs,weights,biases)?);
+        }
+        Self::new(layers)
+    }
+
+    /// Loads a weights file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::read(&mut std::io::BufReader::new(std::fs::File::open(path)?))
+    }
+
+    /// Writes the network in the flat format described in the module docs.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(self.layers.len() as u32).to_le_bytes())?;
+        for layer in &self.layers {
+            writer.write_all(&(layer.inputs as u32).to_le_bytes())?;
+            writer.write_all(&(layer.outputs as u32).to_le_bytes())?;
+            for x in layer.weights.iter().chain(layer.biases.iter()) {
+                writer.write_all(&x.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Scores every action for an encoded observation.
+    pub fn forward(&self, observation: &[f32]) -> Vec<f32> {
+        let mut x = observation.to_vec();
+        for (i,layer) in self.layers.iter().enumerate() {
+            x = layer.forward(&x);
+            if i + 1 < self.layers.len() {
+                x.iter_mut().for_each(|v| *v = v.max(0.0));
+            }
+        }
+        x
+    }
+}
+
+/// A player that picks moves with an [`Mlp`] policy.
+/// With a temperature of zero it always plays the best scoring legal action, otherwise it samples
+/// from the softmax of the scores divided by the temperature. If no legal action scores above the
+/// threshold the move is left to a [`HeuristicDurakPlayer`].
+pub struct NeuralDurakPlayer {
+    policy: Mlp,
+    tracker: ObservationTracker,
+    temperature: f32,
+    threshold: f32,
+    rng: StdRng,
+    fallback: HeuristicDurakPlayer,
+}
+
+impl NeuralDurakPlayer {
+    /// Creates a player from a network.
+    pub fn new(policy: Mlp) -> Self {
+        NeuralDurakPlayer {
+            policy,
+            tracker: ObservationTracker::new(),
+            temperature: 0.0,
+            threshold: f32::NEG_INFINITY,
+            rng: StdRng::from_entropy(),
+            fallback: HeuristicDurakPlayer::new(),
+        }
+    }
+
+    /// Creates a player from a weights file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::new(Mlp::load(path)?))
+    }
+
+    /// Sets the sampling temperature. Zero (the default) always plays the best action.
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = temperature.max(0.0);
+        self
+    }
+
+    /// Sets the minimum score a legal action needs for the network's choice to be used.
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Seeds the sampling RNG for reproducible play.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    // returns the chosen action index, or None if the fallback should decide
+    fn choose(&mut self, state: &ToPlayState, decision: Decision) -> Option<usize> {
+        let scores = self.policy.forward(&self.tracker.encode(state,decision));
+        let mask = legal_action_mask(state,decision);
+        let legal: Vec<(usize,f32)> = (0..ACTION_LEN).filter(|&i| mask[i]).map(|i| (i,scores[i])).collect();
+        let best = legal.iter().copied().max_by(|a,b| a.1.total_cmp(&b.1))?;
+        if best.1 <= self.threshold { return None; }
+        if self.temperature == 0.0 { return Some(best.0); }
+
+        let weights: Vec<f32> = legal.iter().map(|(_,s)| ((s - best.1) / self.temperature).exp()).collect();
+        let mut pick = self.rng.gen::<f32>() * weights.iter().sum::<f32>();
+        for ((i,_),w) in legal.iter().zip(weights.iter()) {
+            if pick < *w { return Some(*i); }
+            pick -= w;
+        }
+        Some(best.0)
+    }
+}
+
+#[async_trait]
+impl DurakPlayer for NeuralDurakPlayer {
+    async fn attack(&mut self, state: &ToPlayState) -> Result<Action> {
+        self.tracker.update(state);
+        match self.choose(state,Decision::Attack).and_then(|i| action_for(state,Decision::Attack,i)) {
+            Some(action) => Ok(action),
+            None => self.fallback.attack(state).await,
+        }
+    }
+
+    async fn defend(&mut self, state: &ToPlayState) -> Result<Action> {
+        self.tracker.update(state);
+        match self.choose(state,Decision::Defend).and_then(|i| action_for(state,Decision::Defend,i)) {
+            Some(action) => Ok(action),
+            None => self.fallback.defend(state).await,
+        }
+    }
+
+    async fn pile_on(&mut self, state: &ToPlayState) -> Result<Vec<Card>> {
+        self.tracker.update(state);
+        // pick one card at a time until the policy passes
+        let mut partial = state.clone();
+        let mut cards = Vec::new();
+        loop {
+            match self.choose(&partial,Decision::PileOn) {
+                None => return self.fallback.pile_on(state).await,
+                Some(PASS) => return Ok(cards),
+                Some(i) => {
+                    let Some(Action::Play(card)) = decode_action(i) else { return Ok(cards); };
+                    cards.push(card);
+                    partial.hand = std::borrow::Cow::Owned(partial.hand.iter().filter(|c| **c != card).copied().collect());
+                    partial.table.to_mut().add_attack(card);
+                },
+            }
+        }
+    }
+
+    async fn exchange_trump(&mut self, state: &ToPlayState) -> Result<bool> {
+        self.fallback.exchange_trump(state).await
+    }
+
+    async fn observe_move(&mut self, state: &ToPlayState, _events: &[MoveEvent]) -> Result<()> {
+        self.tracker.update(state);
+        Ok(())
+    }
+
+    async fn seated(&mut self, id: u64, _seat: usize, _rules: &Rules) -> Result<()> {
+        self.tracker.set_id(id);
+        Ok(())
+    }
+
+    async fn join(&mut self, _player_info: &[PlayerInfo]) -> Result<Profile> {
+        Ok(Profile::bot("Policy"))
+    }
+
+    async fn won(&mut self) -> Result<Ready> {
+        self.tracker.reset();
+        Ok(Ready::Yes)
+    }
+
+    async fn lost(&mut self) -> Result<Ready> {
+        self.tracker.reset();
+        Ok(Ready::Yes)
+    }
+
+    async fn draw(&mut self) -> Result<Ready> {
+        self.tracker.reset();
+        Ok(Ready::Yes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer(inputs: usize, outputs: usize) -> Layer {
+        let weights = (0..inputs * outputs).map(|i| (i % 7) as f32 * 0.1 - 0.3).collect();
+        let biases = (0..outputs).map(|i| i as f32 * 0.01).collect();
+        Layer::new(inputs,outputs,weights,biases).unwrap()
+    }
+
+    #[test]
+    fn weights_round_trip() {
+        let mlp = Mlp::new(vec![layer(OBS_LEN,8),layer(8,ACTION_LEN)]).unwrap();
+        let mut bytes = Vec::new();
+        mlp.write(&mut bytes).unwrap();
+        assert_eq!(&bytes[..8],MAGIC);
+        assert_eq!(bytes.len(),8 + 4 + 2 * 8 + 4 * (OBS_LEN * 8 + 8 + 8 * ACTION_LEN + ACTION_LEN));
+        let read = Mlp::read(&mut bytes.as_slice()).unwrap();
+        let observation: Vec<f32> = (0..OBS_LEN).map(|i| (i % 3) as f32).collect();
+        assert_eq!(read.forward(&observation),mlp.forward(&observation));
+        assert_eq!(mlp.forward(&observation).len(),ACTION_LEN);
+    }
+
+    #[test]
+    fn shape_errors() {
+        assert!(Layer::new(2,3,vec![0.0; 5],vec![0.0; 3]).is_err());
+        assert!(Layer::new(2,3,vec![0.0; 6],vec![0.0; 2]).is_err());
+        assert!(Mlp::new(Vec::new()).is_err());
+        assert!(Mlp::new(vec![layer(OBS_LEN + 1,ACTION_LEN)]).is_err());
+        assert!(Mlp::new(vec![layer(OBS_LEN,ACTION_LEN - 1)]).is_err());
+        assert!(Mlp::new(vec![layer(OBS_LEN,8),layer(9,ACTION_LEN)]).is_err());
+        assert!(Mlp::read(&mut &b"NOTDURAK\0\0\0\0"[..]).is_err());
+
+        // a truncated file
+        let mut bytes = Vec::new();
+        Mlp::new(vec![layer(OBS_LEN,ACTION_LEN)]).unwrap().write(&mut bytes).unwrap();
+        bytes.truncate(bytes.len() - 1);
+        assert!(Mlp::read(&mut bytes.as_slice()).is_err());
+    }
+}
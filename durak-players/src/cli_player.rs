@@ -5,6 +5,7 @@ use async_trait::async_trait;
 use tracing::warn;
 
 use durak_core::prelude::*;
+use durak_core::toplaystate::ValidationError;
 
 fn print_cards(cards: &[Card], trump: Suit) {
     for c in cards {
@@ -16,185 +17,347 @@ fn print_cards(cards: &[Card], trump: Suit) {
     }
 }
 
-fn print_card_stack(state: &ToPlayState) {
+// prints each defense card under the attack card it beat
+fn print_table(table: &Table, trump: Suit) {
+    print!("A:  ");
+    for slot in table.slots() {
+        print_cards(&[slot.attack],trump);
+    }
+    println!();
+    println!();
+    print!("D:  ");
+    for slot in table.slots() {
+        match slot.defense {
+            Some(card) => print_cards(&[card],trump),
+            None => print!("{:>5}","-"),
+        }
+    }
+    println!();
+    println!();
+}
+
+// a player's avatar and name, cut down to fit a column of `width`
+fn short_name(profile: &Profile, width: usize) -> String {
+    let name = match &profile.avatar {
+        Some(avatar) => format!("{} {}",avatar,profile.name),
+        None => profile.name.clone(),
+    };
+    name.chars().take(width - 1).collect()
+}
+
+// shows the viewer first, then everyone else in seat order from their left round to their right
+fn print_card_stack(state: &ToPlayState, viewer: u64) {
     let s = 5;
+    let players: Vec<&PlayerInfo> = match state.player_info.iter().position(|info| info.id == viewer) {
+        Some(seat) => std::iter::once(&state.player_info[seat]).chain(state.others_from(seat)).collect(),
+        None => state.player_info.iter().collect(),
+    };
+    let last = players.len() - 1;
 
-    let bold_to_play = |id, func: &dyn Fn() -> ()| {
+    let bold_to_play = |id, func: &dyn Fn()| {
         if id == state.player_info[state.to_play].id { print!("\x1b[31m"); }
         func();
         if id == state.player_info[state.to_play].id { print!("\x1b[0m"); }
     };
 
-    for info in state.player_info.iter() {
+    for info in players.iter() {
         bold_to_play(info.id, &|| {
-            print!("{}{:>s$}",format!("  {}   ",info.id),"");
+            print!("{:<w$}",short_name(&info.profile,6 + s),w = 6 + s);
         });
     }
-    println!("");
+    println!();
 
-    for info in state.player_info.iter() {
+    for info in players.iter() {
         bold_to_play(info.id, &|| {
-            print!("{}{:>s$}","┌──┐  ","");
+            print!("┌──┐  {:>s$}","");
         });
     }
-    println!("");
+    println!();
 
-    for info in state.player_info.iter() {
+    for info in players.iter() {
         bold_to_play(info.id, &|| {
-            print!("{}{:>s$}","│┌─┴┐ ","");
+            print!("│┌─┴┐ {:>s$}","");
         });
     }
-    println!("");
+    println!();
 
-    for info in state.player_info.iter() {
+    for info in players.iter() {
         bold_to_play(info.id, &|| {
-            print!("{}{:>s$}","└┤┌─┴┐","");
+            print!("└┤┌─┴┐{:>s$}","");
         });
     }
-    println!("");
+    println!();
 
-    for info in state.player_info.iter() {
+    for info in players.iter() {
         bold_to_play(info.id, &|| {
-            print!("{}{:>s$}",format!(" └┤{:>2}│",info.hand_len),"");
+            print!(" └┤{:>2}│{:>s$}",info.hand_len,"");
         });
     }
-    println!("");
+    println!();
 
-    for info in state.player_info.iter() {
+    for info in players.iter() {
         bold_to_play(info.id, &|| {
-            print!("{}{:>s$}","  └──┘","");
+            print!("  └──┘{:>s$}","");
         });
     }
-    println!("");
+    println!();
+
+    if players[0].id == viewer {
+        for i in 0..players.len() {
+            let seat = match i {
+                0 => "(you)",
+                1 if last == 1 => "",
+                1 => "(left)",
+                i if i == last => "(right)",
+                _ => "",
+            };
+            print!("{:<w$}",seat,w = 6 + s);
+        }
+        println!();
+    }
+
+    if players.iter().any(|info| info.profile.bot) {
+        for info in players.iter() {
+            print!("{:<w$}",if info.profile.bot { "(bot)" } else { "" },w = 6 + s);
+        }
+        println!();
+    }
+
+    // partners share a team number
+    if players.iter().any(|info| info.team.is_some()) {
+        for info in players.iter() {
+            match info.team {
+                Some(team) => print!("{:<w$}",format!("Team {}",team),w = 6 + s),
+                None => print!("{:<w$}","",w = 6 + s),
+            }
+        }
+        println!();
+    }
+}
+
+// what the player typed on their turn
+enum Input {
+    Number(usize),
+    // q to forfeit, or u to take back their last move
+    Other(Action),
+    Invalid(anyhow::Error),
+}
+
+// the keys for forfeiting and taking back moves, for prompts
+fn other_moves(state: &ToPlayState) -> &'static str {
+    match state.takeback {
+        true => "q forfeits, u takes back your last move",
+        false => "q forfeits",
+    }
 }
 
 pub struct CliPlayer {
     id: u64,
+    name: String, // as shown to the others, the engine may have changed it to keep it unique
 }
 
 impl CliPlayer {
     pub fn new(id: u64) -> Self {
-        CliPlayer { id }
+        CliPlayer { id, name: String::new() }
+    }
+
+    fn print_name(&mut self, state: &ToPlayState) {
+        if let Some(info) = state.player_info.iter().find(|info| info.id == self.id) {
+            self.name = info.profile.name.clone();
+        }
+        println!("Player: {}",self.name);
     }
 
     fn display_game_state(&self, state: &ToPlayState) {
 
-        print_card_stack(state);
+        print_card_stack(state,self.id);
 
-        println!("");
-        print!("A:  "); print_cards(&state.attack_cards,state.trump); println!("");
-        println!("");
-        print!("D:  "); print_cards(&state.defense_cards,state.trump); println!("");
-        println!("");
+        println!();
+        print!("Trump: "); print_cards(&[state.trump_card],state.trump);
+        println!("    Talon: {}    Discarded: {}",state.draw_pile_len,state.discard_len);
+        println!();
+        print_table(&state.table,state.trump);
 
         print_cards(&state.hand,state.trump);
 
-        println!("");
+        println!();
         for x in 0..state.hand.len() {
             print!("{:>5}", x+1);
         }
         print!("{:>5}", 0);
-        println!("");
+        println!();
+    }
+
+    // asks which of several attack cards to beat, None to pick a different card
+    fn choose_attack(&self, attacks: &[Card], trump: Suit) -> Result<Option<Card>> {
+        println!("Which attack are you beating?");
+        print_cards(attacks,trump);
+        println!();
+        for x in 0..attacks.len() {
+            print!("{:>5}", x+1);
+        }
+        print!("{:>5}", 0);
+        println!();
+        loop {
+            match self.get_input::<usize>() {
+                Err(e) => { warn!("Input error: {}",e); },
+                Ok(0) => { return Ok(None); },
+                Ok(x) if x > attacks.len() => { continue; },
+                Ok(x) => { return Ok(Some(attacks[x-1])); },
+            }
+        }
+    }
+
+    // toggles cards in and out of a selection until 0 is entered with a valid selection, or the
+    // player forfeits or takes back a move instead
+    fn select_cards<F>(&self, hand: &[Card], takeback: bool, validate: F) -> Result<Result<Vec<Card>,Action>>
+    where F: Fn(&[Card]) -> Result<(),ValidationError> {
+        let mut inds: Vec<usize> = Vec::new();
+        loop {
+            for i in 0..hand.len() {
+                if inds.contains(&(i+1)) {
+                    print!("{:>5}","^");
+                } else {
+                    print!("{:>5}","");
+                }
+            }
+            println!();
+            match self.get_move(takeback)? {
+                Input::Other(action) => { return Ok(Err(action)); },
+                Input::Invalid(e) => { warn!("Input error: {}", e); },
+                Input::Number(0) => {
+                    let output: Vec<Card> = inds.iter().map(|x| hand[x - 1]).collect();
+                    match validate(&output) {
+                        Ok(_) => return Ok(Ok(output)),
+                        Err(e) => { warn!("Validation error: {}", e); },
+                    }
+                },
+                Input::Number(x) if x > hand.len() => { continue; }
+                Input::Number(x) => {
+                    if inds.contains(&x) {
+                        inds.retain(|&i| i != x);
+                    } else {
+                        inds.push(x);
+                    }
+                },
+            }
+        }
     }
 
     fn get_input<T: std::str::FromStr<Err=std::num::ParseIntError>>(&self) -> Result<T> {
+        let buf = self.read_line()?;
+        buf.parse().map_err(|e| anyhow!("{:?}",e))
+    }
+
+    // like get_input, but also takes q to forfeit and, if allowed, u to take back a move
+    fn get_move(&self, takeback: bool) -> Result<Input> {
+        let buf = self.read_line()?;
+        Ok(match buf.as_str() {
+            "q" => Input::Other(Action::Forfeit),
+            "u" if takeback => Input::Other(Action::Undo),
+            _ => match buf.parse() {
+                Ok(x) => Input::Number(x),
+                Err(e) => Input::Invalid(anyhow!("{:?}",e)),
+            },
+        })
+    }
+
+    fn read_line(&self) -> Result<String> {
         print!("Your move:  ");
         std::io::stdout().flush()?;
 
         let mut buf = String::new();
         std::io::stdin().read_line(&mut buf)?;
-        let buf = buf.trim_end_matches(char::is_whitespace);
-        buf.parse().map_err(|e| anyhow!("{:?}",e))
+        Ok(buf.trim_end_matches(char::is_whitespace).to_owned())
     }
 }
 
 #[async_trait]
 impl DurakPlayer for CliPlayer {
     async fn attack(&mut self, state: &ToPlayState) -> Result<Action> {
-        println!("Player ID: {}", self.id);
-        println!("You are attacking");
+        self.print_name(state);
+        println!("You are attacking, select cards of one rank then 0 (nothing selected passes, {})",other_moves(state));
         self.display_game_state(state);
-
-        loop {
-            match self.get_input::<usize>() {
-                Err(e) => { warn!("Input error: {}",e); },
-                Ok(x) if x == 0 => { return Ok(Action::Pass); },
-                Ok(x) if x > state.hand.len() => { warn!("Input out of range"); },
-                Ok(x) => {
-                    match state.validate_attack(&Action::Play(state.hand[x-1])) {
-                        Ok(_) => return Ok(Action::Play(state.hand[x-1])),
-                        Err(_) => { warn!("Disallowed attack card"); },
-                    }
-                },
+        let cards = self.select_cards(&state.hand, state.takeback, |cards| {
+            match cards.is_empty() {
+                true => Ok(()),
+                false => state.validate_attack(&Action::attack_with(cards.to_vec())),
             }
-        }
-
+        })?;
+        Ok(cards.map_or_else(|action| action,Action::attack_with))
     }
 
     async fn defend(&mut self, state: &ToPlayState) -> Result<Action> {
-        println!("Player ID: {}", self.id);
-        println!("You are defending");
+        self.print_name(state);
+        println!("You are defending (0 takes the cards, {})",other_moves(state));
         self.display_game_state(state);
 
         loop {
-            match self.get_input::<usize>() {
-                Err(e) => { warn!("Input error: {}",e); },
-                Ok(x) if x == 0 => { return Ok(Action::Pass); },
-                Ok(x) if x > state.hand.len() => { continue; }
-                Ok(x) if state.validate_defense(&Action::Play(state.hand[x-1])).is_ok() => { return Ok(Action::Play(state.hand[x-1])); },
-                _ => continue
+            match self.get_move(state.takeback)? {
+                Input::Other(action) => { return Ok(action); },
+                Input::Invalid(e) => { warn!("Input error: {}",e); },
+                Input::Number(0) => { return Ok(Action::Pass); },
+                Input::Number(x) if x > state.hand.len() => { continue; }
+                Input::Number(x) => {
+                    let card = state.hand[x-1];
+                    let attacks = state.beatable_attacks(&card);
+                    match attacks.len() {
+                        0 => { warn!("Disallowed defense card"); },
+                        1 => { return Ok(Action::Defend { card, against: attacks[0] }); },
+                        _ => {
+                            if let Some(against) = self.choose_attack(&attacks,state.trump)? {
+                                return Ok(Action::Defend { card, against });
+                            }
+                        },
+                    }
+                },
             }
         }
     }
 
     async fn pile_on(&mut self, state: &ToPlayState) -> Result<Vec<Card>> {
-        println!("Player ID: {}", self.id);
+        self.print_name(state);
         println!("You are piling on");
         self.display_game_state(state);
-        let mut inds = std::collections::HashSet::new();
+        // there's no forfeiting or taking back in the middle of a pile on, q just piles on nothing
+        Ok(self.select_cards(&state.hand, false, |cards| state.validate_pile_on(cards))?.unwrap_or_default())
+    }
+
+    async fn exchange_trump(&mut self, state: &ToPlayState) -> Result<bool> {
+        self.print_name(state);
+        self.display_game_state(state);
+        println!("Exchange your {} for the {}? (1 = yes, 0 = no)",Card { rank: Rank::Six, suit: state.trump },state.trump_card);
         loop {
-            for i in 0..state.hand.len() {
-                if inds.contains(&(i+1)) {
-                    print!("{:>5}","^");
-                } else {
-                    print!("{:>5}","");
-                }
-            }
-            println!("");
             match self.get_input::<usize>() {
-                Err(e) => { warn!("Input error: {}", e); },
-                Ok(x) if x == 0 => {
-                    let output: Vec<Card> = inds.iter().map(|x| state.hand[x - 1]).collect();
-                    match state.validate_pile_on(&output) {
-                        Ok(_) => return Ok(output),
-                        Err(e) => { warn!("Validation error: {}", e); },
-                    }
-                },
-                Ok(x) if x > state.hand.len() => { continue; }
-                Ok(x) => {
-                    if inds.contains(&x) {
-                        inds.remove(&x);
-                    } else {
-                        inds.insert(x);
-                    }
-                },
+                Err(e) => { warn!("Input error: {}",e); },
+                Ok(0) => { return Ok(false); },
+                Ok(1) => { return Ok(true); },
+                Ok(_) => { continue; },
             }
         }
     }
 
-    async fn observe_move(&mut self, state: &ToPlayState) -> Result<()> {
-        println!("Player ID: {}", self.id);
+    async fn observe_move(&mut self, state: &ToPlayState, events: &[MoveEvent]) -> Result<()> {
+        for event in events {
+            println!("{}",event);
+        }
+        self.print_name(state);
         self.display_game_state(state);
         Ok(())
     }
 
     async fn won(&mut self) -> Result<Ready> {
-        println!("Congratulations, Player #{}\nYOU WON!!!", self.id);
+        println!("Congratulations, {}\nYOU WON!!!", self.name);
         Ok(Ready::Yes)
     }
 
     async fn lost(&mut self) -> Result<Ready> {
-        println!("I'm sorry, Player #{}\nYou lost.", self.id);
+        println!("I'm sorry, {}\nYou lost.", self.name);
+        Ok(Ready::Yes)
+    }
+
+    async fn draw(&mut self) -> Result<Ready> {
+        println!("It's a draw, {}\nNobody lost.", self.name);
         Ok(Ready::Yes)
     }
 
@@ -210,25 +373,34 @@ impl DurakPlayer for CliPlayer {
         Ok(())
     }
 
-    async fn get_id(&mut self,player_info: &Vec<PlayerInfo>) -> Result<u64> {
+    async fn seated(&mut self, id: u64, _seat: usize, _rules: &Rules) -> Result<()> {
+        self.id = id;
+        Ok(())
+    }
+
+    async fn join(&mut self, player_info: &[PlayerInfo]) -> Result<Profile> {
         println!("Player List:");
         for info in player_info {
-            println!("Player {}",info.id);
+            println!("{}",info.profile);
         }
+        print!("Enter your name:  ");
+        std::io::stdout().flush()?;
+        let mut name = String::new();
+        std::io::stdin().read_line(&mut name)?;
+        self.name = name.trim().to_owned();
+        Ok(Profile::new(&self.name))
+    }
+
+    async fn choose_seat(&mut self, player_info: &[PlayerInfo], seats: usize) -> Result<Option<usize>> {
+        println!("Choose a seat from 1 to {}, 0 for any seat ({} already joined)",seats,player_info.len());
         loop {
-            match self.get_input() {
+            match self.get_input::<usize>() {
                 Err(e) => { warn!("Input error: {}",e); },
-                Ok(x) => {
-                    if !player_info.iter().map(|info| info.id).collect::<Vec<_>>().contains(&x) {
-                        self.id = x;
-                        break;
-                    } else {
-                        println!("That ID is already taken.");
-                    }
-                },
+                Ok(0) => { return Ok(None); },
+                Ok(x) if x > seats => { println!("There are only {} seats, choose from 1 to {}",seats,seats); },
+                Ok(x) => { return Ok(Some(x - 1)); },
             }
         }
-        Ok(self.id)
     }
 }
 
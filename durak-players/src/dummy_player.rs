@@ -8,6 +8,12 @@ pub struct DummyDurakPlayer {
     wait: u64,
 }
 
+impl Default for DummyDurakPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DummyDurakPlayer {
     pub fn new() -> Self {
         Self { id: 1, wait: 0 }
@@ -40,8 +46,8 @@ impl DurakPlayer for DummyDurakPlayer {
     async fn defend(&mut self, state: &ToPlayState) -> Result<Action> {
         self.wait();
         for &card in state.hand.iter() {
-            if state.validate_defense(&Action::Play(card)).is_ok() {
-                return Ok(Action::Play(card));
+            if let Some(&against) = state.beatable_attacks(&card).first() {
+                return Ok(Action::Defend { card, against });
             }
         }
         Ok(Action::Pass)
@@ -52,12 +58,12 @@ impl DurakPlayer for DummyDurakPlayer {
         Ok(Vec::new())
     }
 
-    async fn get_id(&mut self, player_info: &Vec<PlayerInfo>) -> Result<u64> {
-        for info in player_info {
-            if self.id <= info.id {
-                self.id = info.id + 1;
-            }
-        }
-        Ok(self.id)
+    async fn seated(&mut self, id: u64, _seat: usize, _rules: &Rules) -> Result<()> {
+        self.id = id;
+        Ok(())
+    }
+
+    async fn join(&mut self, _player_info: &[PlayerInfo]) -> Result<Profile> {
+        Ok(Profile::bot("Dummy"))
     }
 }
@@ -33,7 +33,7 @@ impl<T: DurakPlayer> NetClientDurakPlayer<T> {
     fn stream_write<U: Serialize>(&mut self, data: U) -> Result<()> {
         let content = serde_json::to_string(&data)?;
         let mut stream = BufWriter::new(&mut self.stream);
-        stream.write(content.as_bytes())?;
+        stream.write_all(content.as_bytes())?;
         stream.flush()?;
         Ok(())
     }
@@ -47,7 +47,7 @@ impl<T: DurakPlayer> NetClientDurakPlayer<T> {
     //     let ret = func(self,&data).await?;
     //     let content = serde_json::to_string(&ret)?;
     //     let mut stream = BufWriter::new(&mut self.stream);
-    //     stream.write(content.as_bytes())?;
+    //     stream.write_all(content.as_bytes())?;
     //     stream.flush()?;
     //     Ok(())
     // }
@@ -85,18 +85,30 @@ impl<T: DurakPlayer> NetClientDurakPlayer<T> {
             Some("O") => {
                 // self.process_query(|player,state| player.engine.observe_move(state)).await?;
                 
+                let (state,events): (ToPlayState,Vec<MoveEvent>) = self.stream_read()?;
+                self.engine.observe_move(&state,&events).await?;
+                self.stream_write(())?;
+        
+            },
+            Some("X") => {
                 let state = self.stream_read()?;
-                let ret = self.engine.observe_move(&state).await?;
+                let ret = self.engine.exchange_trump(&state).await?;
                 self.stream_write(ret)?;
-        
             },
-            Some("I") => {
-                // self.process_query(|player,player_info| player.engine.get_id(player_info)).await?;
-                
-                let player_info = self.stream_read()?;
-                let ret = self.engine.get_id(&player_info).await?;
+            Some("J") => {
+                let player_info: Vec<PlayerInfo> = self.stream_read()?;
+                let ret = self.engine.join(&player_info).await?;
                 self.stream_write(ret)?;
-        
+            },
+            Some("C") => {
+                let (player_info,seats): (Vec<PlayerInfo>,usize) = self.stream_read()?;
+                let ret = self.engine.choose_seat(&player_info,seats).await?;
+                self.stream_write(ret)?;
+            },
+            Some("S") => {
+                let (id,seat,rules): (u64,usize,Rules) = self.stream_read()?;
+                self.engine.seated(id,seat,&rules).await?;
+                self.stream_write(())?;
             },
             Some("W") => {
                 self.engine.won().await?;
@@ -106,12 +118,16 @@ impl<T: DurakPlayer> NetClientDurakPlayer<T> {
                 self.engine.lost().await?;
                 return Ok(2);
             },
+            Some("R") => {
+                self.engine.draw().await?;
+                return Ok(4);
+            },
             Some("M") => {
                 // self.process_query(|player: &mut Self, msg: &String| player.engine.message(&msg)).await?;
                 
                 let msg: String = self.stream_read()?;
-                let ret = self.engine.message(&msg).await?;
-                self.stream_write(ret)?;
+                self.engine.message(&msg).await?;
+                self.stream_write(())?;
         
                 return Ok(3);
             },
@@ -119,8 +135,8 @@ impl<T: DurakPlayer> NetClientDurakPlayer<T> {
                 // self.process_query(|player: &mut Self, error_msg: &String| player.engine.error(&error_msg)).await?;
                 
                 let error_msg: String = self.stream_read()?;
-                let ret = self.engine.error(&error_msg).await?;
-                self.stream_write(ret)?;
+                self.engine.error(&error_msg).await?;
+                self.stream_write(())?;
         
                 return Ok(3);
             },
@@ -23,7 +23,9 @@ impl DurakServer {
         match self.listener.accept() {
             Ok((socket,addr)) => {
                 info!("Connection at {}",addr);
-                self.players.push(NetServerDurakPlayer::new(socket));
+                // clients are numbered as they connect
+                let id = self.players.len() as u64;
+                self.players.push(NetServerDurakPlayer::new(socket,id));
             },
             Err(e) => return Err(e.into()),
         }
@@ -1,93 +1,180 @@
 use std::net::TcpStream;
 use std::io::{Write,BufWriter,BufRead,BufReader};
 
-use anyhow::Result;
+use anyhow::{anyhow,Result};
 use async_trait::async_trait;
+use tokio::task::JoinHandle;
 
-// use tracing::{info};
+use tracing::debug;
 use serde::{Serialize,Deserialize};
 
 use durak_core::prelude::*;
 
+// a line read from the client, along with the reader to read the next one
+type Read = Result<(BufReader<TcpStream>,String)>;
+
 pub struct NetServerDurakPlayer {
     pub id: u64,
     stream: TcpStream,
+    reader: Option<BufReader<TcpStream>>,
+    // a reply the game stopped waiting for, still to be read and thrown away
+    pending: Option<JoinHandle<Read>>,
 }
 
 impl NetServerDurakPlayer {
-    pub fn new(stream: TcpStream) -> Self {
+    // a connected client, known to the game by the `id` the server gave it
+    pub fn new(stream: TcpStream, id: u64) -> Self {
         NetServerDurakPlayer {
-            id: 0,
+            id,
             stream,
+            reader: None,
+            pending: None,
+        }
+    }
+
+    // waits for a line from the client without blocking the game, so that it can time out
+    async fn read_line(&mut self) -> Result<String> {
+        if self.pending.is_none() {
+            let mut reader = match self.reader.take() {
+                Some(reader) => reader,
+                None => BufReader::new(self.stream.try_clone()?),
+            };
+            self.pending = Some(tokio::task::spawn_blocking(move || {
+                let mut line = String::new();
+                reader.read_line(&mut line)?;
+                Ok((reader,line))
+            }));
         }
+        // if the game gives up here the read carries on, and is picked up by the next query
+        let read = self.pending.as_mut().ok_or_else(|| anyhow!("No reply pending"))?.await;
+        self.pending = None;
+        let (reader,line) = read??;
+        self.reader = Some(reader);
+        Ok(line)
     }
 
-    fn query_client<A: Serialize, B: for<'b> Deserialize<'b>>(&mut self, sig: &str, data: &A) -> Result<B> {
+    async fn query_client<A: Serialize + Sync, B: for<'b> Deserialize<'b>>(&mut self, sig: &str, data: &A) -> Result<B> {
+        if self.pending.is_some() {
+            let late = self.read_line().await?;
+            debug!("Discarding late reply from player # {}: {}",self.id,late.trim_end());
+        }
         let json = serde_json::to_string(data)?;
         let content = json.as_bytes();
         let mut stream = BufWriter::new(&mut self.stream);
-        stream.write(sig.as_bytes())?;
-        stream.write(content)?;
+        stream.write_all(sig.as_bytes())?;
+        stream.write_all(content)?;
         stream.flush()?;
         drop(stream);
-        let mut stream = BufReader::new(&mut self.stream);
-        let mut data = String::new();
-        let _ = stream.read_line(&mut data)?;
+        let data = self.read_line().await?;
         let ret: B = serde_json::from_str(&data)?;
         Ok(ret)
     }
-
 }
 
 #[async_trait]
 impl DurakPlayer for NetServerDurakPlayer {
     async fn attack(&mut self, state: &ToPlayState) -> Result<Action> {
-        self.query_client("A\n",state)
+        self.query_client("A\n",state).await
     }
 
     async fn defend(&mut self, state: &ToPlayState) -> Result<Action> {
-        self.query_client("D\n",state)
+        self.query_client("D\n",state).await
     }
 
     async fn pile_on(&mut self, state: &ToPlayState) -> Result<Vec<Card>> {
-        self.query_client("P\n",state)
+        self.query_client("P\n",state).await
+    }
+
+    async fn observe_move(&mut self, state: &ToPlayState, events: &[MoveEvent]) -> Result<()> {
+        self.query_client("O\n",&(state,events)).await
+    }
+
+    async fn exchange_trump(&mut self, state: &ToPlayState) -> Result<bool> {
+        self.query_client("X\n",state).await
+    }
+
+    // the server numbers clients as they connect
+    async fn get_id(&mut self, _player_info: &[PlayerInfo]) -> Result<u64> {
+        Ok(self.id)
+    }
+
+    async fn join(&mut self, player_info: &[PlayerInfo]) -> Result<Profile> {
+        self.query_client("J\n",&player_info).await
+    }
+
+    fn is_remote(&self) -> bool {
+        true
     }
 
-    async fn observe_move(&mut self, state: &ToPlayState) -> Result<()> {
-        self.query_client("O\n",state)
+    async fn choose_seat(&mut self, player_info: &[PlayerInfo], seats: usize) -> Result<Option<usize>> {
+        self.query_client("C\n",&(player_info,seats)).await
     }
 
-    async fn get_id(&mut self, player_info: &Vec<PlayerInfo>) -> Result<u64> {
-        self.query_client("I\n",player_info)
+    async fn seated(&mut self, id: u64, seat: usize, rules: &Rules) -> Result<()> {
+        self.id = id;
+        self.query_client("S\n",&(id,seat,rules)).await
     }
 
     async fn won(&mut self) -> Result<Ready> {
         let mut stream = BufWriter::new(&mut self.stream);
-        stream.write("W\n".as_bytes())?;
+        stream.write_all("W\n".as_bytes())?;
         stream.flush()?;
         Ok(Ready::Yes)
     }
 
     async fn lost(&mut self) -> Result<Ready> {
         let mut stream = BufWriter::new(&mut self.stream);
-        stream.write("L\n".as_bytes())?;
+        stream.write_all("L\n".as_bytes())?;
         stream.flush()?;
         Ok(Ready::Yes)
     }
 
-    async fn message(&mut self, msg: &str) -> Result<()> {
+    async fn draw(&mut self) -> Result<Ready> {
         let mut stream = BufWriter::new(&mut self.stream);
-        stream.write("M\n".as_bytes())?;
-        stream.write(msg.as_bytes())?;
+        stream.write_all("R\n".as_bytes())?;
         stream.flush()?;
-        Ok(())
+        Ok(Ready::Yes)
+    }
+
+    // the client acknowledges messages and errors, like any other query
+    async fn message(&mut self, msg: &str) -> Result<()> {
+        self.query_client("M\n",&msg).await
     }
 
     async fn error(&mut self, error: &str) -> Result<()> {
-        let mut stream = BufWriter::new(&mut self.stream);
-        stream.write("E\n".as_bytes())?;
-        stream.write(error.as_bytes())?;
-        stream.flush()?;
-        Ok(())
+        self.query_client("E\n",&error).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn late_replies_are_discarded() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut player = NetServerDurakPlayer::new(TcpStream::connect(listener.local_addr().unwrap()).unwrap(),0);
+        let client = std::thread::spawn(move || {
+            let (mut stream,_) = listener.accept().unwrap();
+            // far too slow to acknowledge the message
+            std::thread::sleep(Duration::from_millis(500));
+            stream.write_all(b"null\n").unwrap();
+            // but answers the join query as soon as it arrives
+            let mut received = Vec::new();
+            let mut buf = [0; 256];
+            while !String::from_utf8_lossy(&received).contains("J\n") {
+                let n = stream.read(&mut buf).unwrap();
+                received.extend_from_slice(&buf[..n]);
+            }
+            let profile = serde_json::to_string(&Profile::new("Late")).unwrap();
+            stream.write_all(format!("{}\n",profile).as_bytes()).unwrap();
+        });
+        // the client blocks on the game's side too, but the game still gets to give up waiting
+        assert!(tokio::time::timeout(Duration::from_millis(50),player.message("hello")).await.is_err());
+        assert_eq!(player.join(&[]).await.unwrap().name,"Late");
+        client.join().unwrap();
     }
 }
@@ -0,0 +1,77 @@
+use durak_core::prelude::*;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// A simple rule of thumb bot. Always plays its cheapest legal card, holds on to trumps when it
+/// isn't forced to use them, and piles on everything it can spare. When leading a round it plays
+/// every non-trump card of its cheapest rank at once.
+#[derive(Default)]
+pub struct HeuristicDurakPlayer;
+
+impl HeuristicDurakPlayer {
+    pub fn new() -> Self {
+        HeuristicDurakPlayer
+    }
+}
+
+// hand sorted from least to most valuable
+fn cheapest_first(state: &ToPlayState) -> Vec<Card> {
+    let mut hand = state.hand.to_vec();
+    sort_cards(&mut hand,state.trump);
+    hand
+}
+
+#[async_trait]
+impl DurakPlayer for HeuristicDurakPlayer {
+    async fn attack(&mut self, state: &ToPlayState) -> Result<Action> {
+        let leading = state.table.is_empty();
+        let hand = cheapest_first(state);
+        for &card in hand.iter() {
+            // only spend trumps when leading the round
+            if !leading && card.suit == state.trump { continue; }
+            if state.validate_attack(&Action::Play(card)).is_ok() {
+                if leading && card.suit != state.trump {
+                    let cards: Vec<Card> = hand
+                        .iter()
+                        .filter(|c| c.rank == card.rank && c.suit != state.trump)
+                        .take(state.attack_limit())
+                        .copied()
+                        .collect();
+                    return Ok(Action::attack_with(cards));
+                }
+                return Ok(Action::Play(card));
+            }
+        }
+        Ok(Action::Pass)
+    }
+
+    async fn defend(&mut self, state: &ToPlayState) -> Result<Action> {
+        // beat the strongest attack we can with our cheapest card
+        for card in cheapest_first(state) {
+            let mut attacks = state.beatable_attacks(&card);
+            sort_cards(&mut attacks,state.trump);
+            if let Some(&against) = attacks.last() {
+                return Ok(Action::Defend { card, against });
+            }
+        }
+        Ok(Action::Pass)
+    }
+
+    async fn pile_on(&mut self, state: &ToPlayState) -> Result<Vec<Card>> {
+        Ok(cheapest_first(state)
+            .into_iter()
+            .filter(|card| card.suit != state.trump && state.validate_pile_on_single(card).is_ok())
+            .take(state.attack_limit())
+            .collect())
+    }
+
+    async fn exchange_trump(&mut self, _state: &ToPlayState) -> Result<bool> {
+        // the face up card is always a better trump than the six
+        Ok(true)
+    }
+
+    async fn join(&mut self, _player_info: &[PlayerInfo]) -> Result<Profile> {
+        Ok(Profile::bot("Heuristic"))
+    }
+}
@@ -1,12 +1,29 @@
 pub use durak_core::prelude::*;
 
 mod dummy_player;
+mod heuristic_player;
+mod mcts_player;
 mod cli_player;
 mod tui_player;
 mod durak_net;
 
 pub use dummy_player::*;
+pub use heuristic_player::*;
+pub use mcts_player::*;
 pub use cli_player::*;
 pub use tui_player::*;
 pub use durak_net::*;
 
+
+/// Names of the built in bots that [`bot_by_name`] knows how to make.
+pub const BOT_NAMES: &[&str] = &["dummy","heuristic","mcts"];
+
+/// Makes one of the built in bots by name.
+pub fn bot_by_name(name: &str) -> Option<Box<dyn DurakPlayer>> {
+    match name {
+        "dummy" => Some(Box::new(DummyDurakPlayer::new())),
+        "heuristic" => Some(Box::new(HeuristicDurakPlayer::new())),
+        "mcts" => Some(Box::new(MctsDurakPlayer::new())),
+        _ => None,
+    }
+}
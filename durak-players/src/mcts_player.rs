@@ -0,0 +1,300 @@
+use durak_core::prelude::*;
+
+use anyhow::{anyhow,Result};
+use async_trait::async_trait;
+use rand::{Rng,SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+const ITERATIONS: usize = 200;
+// how far a rollout plays before calling it even
+const ROLLOUT_MOVES: usize = 300;
+const EXPLORATION: f64 = 0.7;
+
+/// A Monte Carlo tree search bot. Before each move it deals the cards it can't see at random,
+/// plays the game out from there many times, and picks the move that did best. It keeps track of
+/// the cards other players took so those end up in the right hands.
+pub struct MctsDurakPlayer {
+    iterations: usize,
+    rng: StdRng,
+    rules: Rules,
+    known: Vec<Vec<Card>>, // cards each seat is known to hold
+    discarded: Vec<Card>,
+    table: Vec<Card>, // cards played this round, as of the last move
+}
+
+impl Default for MctsDurakPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MctsDurakPlayer {
+    /// Same seed, same moves, so simulations stay reproducible.
+    pub fn new() -> Self {
+        MctsDurakPlayer {
+            iterations: ITERATIONS,
+            rng: StdRng::seed_from_u64(0),
+            rules: Rules::new(),
+            known: Vec::new(),
+            discarded: Vec::new(),
+            table: Vec::new(),
+        }
+    }
+
+    /// Sets how many games are played out per move, more is stronger but slower.
+    pub fn with_iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations.max(1);
+        self
+    }
+
+    /// Seeds the random deals.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    fn search(&mut self, state: &ToPlayState, turn: Turn) -> Result<Action> {
+        let moves = legal_moves(state,turn);
+        if moves.len() == 1 { return Ok(moves[0].clone()); }
+        let mut known = self.known.clone();
+        known.resize(state.player_info.len(),Vec::new());
+        let mut tree = vec![Node::root()];
+        for _ in 0..self.iterations {
+            let playout = Playout::deal(state,turn,&self.rules,&known,&self.discarded,&mut self.rng);
+            iterate(&mut tree,playout,&mut self.rng)?;
+        }
+        tree[0].children.iter()
+            .map(|&child| &tree[child])
+            .max_by(|a,b| a.visits.total_cmp(&b.visits))
+            .and_then(|node| node.action.clone())
+            .ok_or_else(|| anyhow!("search found no moves"))
+    }
+
+    // the seat of the player with `id`
+    fn seat(state: &ToPlayState, id: u64) -> Option<usize> {
+        state.player_info.iter().position(|info| info.id == id)
+    }
+
+    fn forget(&mut self, seat: Option<usize>, card: &Card) {
+        if let Some(hand) = seat.and_then(|seat| self.known.get_mut(seat)) { hand.retain(|c| c != card); }
+    }
+}
+
+// a move in the search tree, with how it went for the player who made it
+struct Node {
+    action: Option<Action>,
+    mover: usize,
+    children: Vec<usize>,
+    visits: f64,
+    available: f64, // times the move could have been played
+    reward: f64,
+}
+
+impl Node {
+    fn root() -> Self {
+        Node { action: None, mover: 0, children: Vec::new(), visits: 0.0, available: 0.0, reward: 0.0 }
+    }
+}
+
+// every move worth searching for the player to move
+fn legal_moves(state: &ToPlayState, turn: Turn) -> Vec<Action> {
+    let mut moves: Vec<Action> = match turn {
+        Turn::Attack => state.hand.iter().filter(|&&card| state.validate_attack(&Action::Play(card)).is_ok()).map(|&card| Action::Play(card)).collect(),
+        Turn::Defense => state.hand.iter()
+            .flat_map(|&card| state.beatable_attacks(&card).into_iter().map(move |against| Action::Defend { card, against }))
+            .collect(),
+        Turn::PileOn => {
+            let cards: Vec<Card> = state.hand.iter().copied().filter(|card| state.validate_pile_on_single(card).is_ok()).take(state.attack_limit()).collect();
+            let mut moves: Vec<Action> = cards.iter().map(|&card| Action::Play(card)).collect();
+            if cards.len() > 1 { moves.push(Action::PlayMany(cards)); }
+            moves
+        },
+    };
+    // leading a round is the only time passing isn't allowed
+    if turn != Turn::Attack || !state.table.is_empty() || moves.is_empty() { moves.push(Action::Pass); }
+    moves
+}
+
+// one ISMCTS iteration on a fresh deal: select down the tree, expand one move, roll out and
+// back up the result
+fn iterate<R: Rng>(tree: &mut Vec<Node>, mut playout: Playout, rng: &mut R) -> Result<()> {
+    let mut path = vec![0];
+    let mut node = 0;
+    while let Some((seat,turn)) = playout.turn() {
+        let moves = legal_moves(&playout.view(),turn);
+        let children: Vec<usize> = tree[node].children.iter()
+            .copied()
+            .filter(|&child| tree[child].mover == seat && tree[child].action.as_ref().is_some_and(|action| moves.contains(action)))
+            .collect();
+        for &child in &children { tree[child].available += 1.0; }
+        let untried: Vec<&Action> = moves.iter().filter(|&action| !children.iter().any(|&child| tree[child].action.as_ref() == Some(action))).collect();
+        if let Some(&action) = untried.choose(rng) {
+            playout.play(action.clone())?;
+            tree.push(Node { action: Some(action.clone()), mover: seat, available: 1.0, ..Node::root() });
+            let child = tree.len() - 1;
+            tree[node].children.push(child);
+            path.push(child);
+            break;
+        }
+        node = *children.iter()
+            .max_by(|&&a,&&b| ucb(&tree[a]).total_cmp(&ucb(&tree[b])))
+            .ok_or_else(|| anyhow!("no moves to search"))?;
+        playout.play(tree[node].action.clone().unwrap())?;
+        path.push(node);
+    }
+    rollout(&mut playout,rng)?;
+    for &ind in &path[1..] {
+        let node = &mut tree[ind];
+        node.visits += 1.0;
+        node.reward += playout.score(node.mover).unwrap_or(0.5);
+    }
+    Ok(())
+}
+
+fn ucb(node: &Node) -> f64 {
+    node.reward / node.visits + EXPLORATION * (node.available.ln() / node.visits).sqrt()
+}
+
+// plays the game on mostly cheapest first, the way most players would
+fn rollout<R: Rng>(playout: &mut Playout, rng: &mut R) -> Result<()> {
+    for _ in 0..ROLLOUT_MOVES {
+        let Some((_,turn)) = playout.turn() else { break; };
+        let view = playout.view();
+        let mut moves = legal_moves(&view,turn);
+        let action = if rng.gen_bool(0.2) {
+            moves.swap_remove(rng.gen_range(0..moves.len()))
+        } else {
+            let trump = view.trump;
+            // trumps last, then by rank, and passing only when there's nothing else
+            moves.into_iter().min_by_key(|action| match action {
+                Action::Play(card) | Action::Defend { card, .. } => (card.suit == trump,card.rank as usize),
+                _ => (true,usize::MAX),
+            }).unwrap()
+        };
+        playout.play(action)?;
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl DurakPlayer for MctsDurakPlayer {
+    async fn attack(&mut self, state: &ToPlayState) -> Result<Action> {
+        self.search(state,Turn::Attack)
+    }
+
+    async fn defend(&mut self, state: &ToPlayState) -> Result<Action> {
+        self.search(state,Turn::Defense)
+    }
+
+    async fn pile_on(&mut self, state: &ToPlayState) -> Result<Vec<Card>> {
+        Ok(match self.search(state,Turn::PileOn)? {
+            Action::Play(card) => vec![card],
+            Action::PlayMany(cards) => cards,
+            _ => Vec::new(),
+        })
+    }
+
+    async fn observe_move(&mut self, state: &ToPlayState, events: &[MoveEvent]) -> Result<()> {
+        self.known.resize(state.player_info.len(),Vec::new());
+        for event in events {
+            match event {
+                MoveEvent::Attacked(id,card) | MoveEvent::Defended(id,card) => {
+                    self.forget(Self::seat(state,*id),card);
+                    if !self.table.contains(card) { self.table.push(*card); }
+                },
+                MoveEvent::PiledOn(id,cards) => {
+                    for card in cards {
+                        self.forget(Self::seat(state,*id),card);
+                        if !self.table.contains(card) { self.table.push(*card); }
+                    }
+                },
+                MoveEvent::TookCards(id,_) => {
+                    let taken = std::mem::take(&mut self.table);
+                    if let Some(seat) = Self::seat(state,*id) { self.known[seat].extend(taken); }
+                },
+                MoveEvent::RoundDiscarded => self.discarded.append(&mut self.table),
+                MoveEvent::ExchangedTrump(id,six,face_up) => {
+                    let seat = Self::seat(state,*id);
+                    self.forget(seat,six);
+                    if let Some(seat) = seat { self.known[seat].push(*face_up); }
+                },
+                MoveEvent::ShowedLowestTrump(id,card) => {
+                    if let Some(seat) = Self::seat(state,*id) { self.known[seat].push(*card); }
+                },
+                _ => {},
+            }
+        }
+        // the table as it is now, in case a move was taken back
+        self.table = state.table.cards().copied().collect();
+        Ok(())
+    }
+
+    async fn exchange_trump(&mut self, _state: &ToPlayState) -> Result<bool> {
+        // the face up card is always a better trump than the six
+        Ok(true)
+    }
+
+    async fn seated(&mut self, _id: u64, _seat: usize, rules: &Rules) -> Result<()> {
+        self.rules = rules.clone();
+        self.known.clear();
+        self.discarded.clear();
+        self.table.clear();
+        Ok(())
+    }
+
+    async fn join(&mut self, _player_info: &[PlayerInfo]) -> Result<Profile> {
+        Ok(Profile::bot("MCTS"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+
+    use crate::DummyDurakPlayer;
+    use durak_core::game::BOT_ROUND_LIMIT;
+
+    #[tokio::test]
+    async fn beats_dummy_bots() {
+        // evenly matched bots would lose about half
+        let mut lost = 0;
+        for seed in 0..20 {
+            let mut game = DurakGame::new().with_round_limit(BOT_ROUND_LIMIT);
+            game.add_player(Box::new(MctsDurakPlayer::new().with_iterations(50).with_seed(seed))).await.unwrap();
+            game.add_player(Box::new(DummyDurakPlayer::new())).await.unwrap();
+            game.init(&mut StdRng::seed_from_u64(seed)).unwrap();
+            let result = game.run_game().await.unwrap();
+            if result.stats().durak == Some(0) { lost += 1; }
+        }
+        assert!(lost < 10,"lost {} of 20 games to the dummy bot",lost);
+    }
+
+    #[tokio::test]
+    async fn remembers_taken_cards() {
+        let card = |i| Card::try_from(i).unwrap();
+        let info = |id| PlayerInfo { id, profile: Profile::default(), hand_len: 6, team: None };
+        let player_info = vec![info(1),info(2)];
+        let state = ToPlayState {
+            trump: Suit::Hearts,
+            trump_card: card(0),
+            draw_pile_len: 10,
+            discard_len: 0,
+            table: Cow::Owned(Table::new()),
+            hand: Cow::Owned(Vec::new()),
+            player_info: Cow::Owned(player_info),
+            last_attacker: 0,
+            defender: 1,
+            to_play: 0,
+            takeback: false,
+        };
+        let mut bot = MctsDurakPlayer::new();
+        bot.seated(1,0,&Rules::new()).await.unwrap();
+        bot.observe_move(&state,&[MoveEvent::Attacked(1,card(3)),MoveEvent::Defended(2,card(4)),MoveEvent::PiledOn(1,vec![card(5)]),MoveEvent::TookCards(2,3)]).await.unwrap();
+        assert_eq!(bot.known[1],vec![card(3),card(4),card(5)]);
+        bot.observe_move(&state,&[MoveEvent::Attacked(2,card(4)),MoveEvent::Defended(1,card(6)),MoveEvent::RoundDiscarded]).await.unwrap();
+        assert_eq!(bot.known[1],vec![card(3),card(5)]);
+        assert_eq!(bot.discarded,vec![card(4),card(6)]);
+    }
+}
@@ -12,12 +12,22 @@ use cursive::views::{HideableView,DummyView,TextView,LinearLayout,Dialog,PaddedV
 
 use durak_core::prelude::*;
 
+// number of moves shown in the move log
+const MOVE_LOG_LEN: usize = 5;
+
 pub struct TuiPlayer {
     id: u64,
+    name: String, // as shown to the others, the engine may have changed it to keep it unique
     tui: CbSink,
     handle: Option<std::thread::JoinHandle<()>>,
 }
 
+impl Default for TuiPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TuiPlayer {
     pub fn new() -> Self {
         let (sender, receiver) = bounded::<CbSink>(0);
@@ -44,14 +54,17 @@ impl TuiPlayer {
         let tui = receiver.recv().unwrap();
         TuiPlayer {
             id: 0,
+            name: String::new(),
             tui,
             handle: Some(handle),
         }
     }
 
-    fn test_recv<T>(&mut self, receiver: Receiver<T>) -> Result<T> {
+    // waits for the TUI off the async runtime, so the game can time out observers meanwhile
+    async fn test_recv<T: Send + 'static>(&mut self, receiver: Receiver<T>) -> Result<T> {
         debug!("test recv");
-        let thing = match receiver.recv() {
+        let received = tokio::task::spawn_blocking(move || receiver.recv()).await?;
+        let thing = match received {
             Ok(x) => x,
             Err(e) => {
                 error!("receiver error");
@@ -60,7 +73,7 @@ impl TuiPlayer {
                     std::thread::sleep(std::time::Duration::from_millis(1000));
                     s.pop_layer();
                     s.quit();
-                }),std::time::Duration::from_millis(10000)).unwrap();
+                }),std::time::Duration::from_millis(10000)).ok();
                 eprintln!("ERROR!");
                 bail!("Receiver error: {}" ,e);
             },
@@ -86,24 +99,21 @@ impl DurakPlayer for TuiPlayer {
         let (sender,receiver) = bounded::<Action>(0);
         let id = self.id;
         let static_state = state.to_static();
-        self.tui.send(Box::new(move |s| {
+        // pressing q closes the window, which leaves the game
+        if self.tui.send(Box::new(move |s| {
             update_game_state_basic(s,&static_state,id);
-            update_game_state_hand_dialog_a_d(s,&static_state,id,sender);
-        })).map_err(|e| anyhow!("Send Error: {:?}",e))?;
+            update_game_state_hand_dialog_attack(s,&static_state,id,sender);
+        })).is_err() { return Ok(Action::Forfeit); }
         loop {
             debug!("loop");
-            match self.test_recv(receiver.clone()) {
-                Ok(Action::Play(card)) => {
-                    debug!("Received card");
-                    match state.validate_attack(&Action::Play(card)) {
-                        Ok(_) => return Ok(Action::Play(card)),
-                        Err(_) => {},
-                    }
+            match self.test_recv(receiver.clone()).await {
+                Ok(action) => {
+                    debug!("Received action");
+                    if state.validate_attack(&action).is_ok() { return Ok(action) }
                 },
-                Ok(Action::Pass) => { return Ok(Action::Pass); },
-                Err(e) => { return Err(e); },
+                Err(_) => { return Ok(Action::Forfeit); },
             }
-            std::thread::sleep(std::time::Duration::from_millis(100));
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
         }
     }
 
@@ -111,20 +121,16 @@ impl DurakPlayer for TuiPlayer {
         let (sender,receiver) = bounded::<Action>(0);
         let id = self.id;
         let static_state = state.to_static();
-        self.tui.send(Box::new(move |s| {
+        if self.tui.send(Box::new(move |s| {
             update_game_state_basic(s,&static_state,id);
-            update_game_state_hand_dialog_a_d(s,&static_state,id,sender);
-        })).map_err(|e| anyhow!("Send Error: {:?}",e))?;
+            update_game_state_hand_dialog_defend(s,&static_state,id,sender);
+        })).is_err() { return Ok(Action::Forfeit); }
         loop {
-            match self.test_recv(receiver.clone()) {
-                Ok(Action::Play(card)) => {
-                    match state.validate_defense(&Action::Play(card)) {
-                        Ok(_) => return Ok(Action::Play(card)),
-                        Err(_) => {},
-                    }
+            match self.test_recv(receiver.clone()).await {
+                Ok(action) => {
+                    if state.validate_defense(&action).is_ok() { return Ok(action) }
                 },
-                Ok(Action::Pass) => { return Ok(Action::Pass); },
-                Err(e) => { return Err(e); },
+                Err(_) => { return Ok(Action::Forfeit); },
             }
         }
     }
@@ -139,64 +145,75 @@ impl DurakPlayer for TuiPlayer {
             update_game_state_hand_dialog_pile_on(s,&static_state,id,sender);
         })).map_err(|e| anyhow!("Send Error: {:?}",e))?;
         loop {
-            match self.test_recv(receiver.clone()) {
+            match self.test_recv(receiver.clone()).await {
                 Ok(pile_on_cards) => {
-                    match state.validate_pile_on(&pile_on_cards) {
-                        Ok(_) => return Ok(pile_on_cards),
-                        Err(_) => {},
-                    }
+                    if state.validate_pile_on(&pile_on_cards).is_ok() { return Ok(pile_on_cards) }
                 },
                 Err(e) => { return Err(e); },
             }
         }
     }
 
-    async fn observe_move(&mut self, state: &ToPlayState) -> Result<()> {
+    async fn exchange_trump(&mut self, state: &ToPlayState) -> Result<bool> {
+        let (sender,receiver) = bounded::<bool>(0);
         let id = self.id;
         let static_state = state.to_static();
         self.tui.send(Box::new(move |s| {
             update_game_state_basic(s,&static_state,id);
-            update_game_state_hand_dialog_observe(s,&static_state,id);
+            update_game_state_hand_dialog_exchange(s,&static_state,sender);
         })).map_err(|e| anyhow!("Send Error: {:?}",e))?;
+        self.test_recv(receiver).await
+    }
+
+    async fn observe_move(&mut self, state: &ToPlayState, events: &[MoveEvent]) -> Result<()> {
+        let id = self.id;
+        if let Some(info) = state.player_info.iter().find(|info| info.id == id) {
+            self.name = info.profile.name.clone();
+        }
+        let static_state = state.to_static();
+        let events = events.to_vec();
+        // the window may have been closed, the player forfeits on their next turn
+        self.tui.send(Box::new(move |s| {
+            update_move_log(s,&events);
+            update_game_state_basic(s,&static_state,id);
+            update_game_state_hand_dialog_observe(s,&static_state,id);
+        })).ok();
         Ok(())
     }
 
-    async fn get_id(&mut self, _player_info: &Vec<PlayerInfo>) -> Result<u64> {
-        let player_info = _player_info.clone();
-        let (sender,receiver) = bounded::<u64>(0);
+    async fn seated(&mut self, id: u64, _seat: usize, _rules: &Rules) -> Result<()> {
+        self.id = id;
+        Ok(())
+    }
+
+    async fn join(&mut self, player_info: &[PlayerInfo]) -> Result<Profile> {
+        let player_info = player_info.to_vec();
+        let (sender,receiver) = bounded::<String>(0);
         self.tui.send(Box::new(move |s| {
-            s.call_on_name("get_id", move |hideable: &mut HideableView<PaddedView<LinearLayout>>| {
+            s.call_on_name("join", move |hideable: &mut HideableView<PaddedView<LinearLayout>>| {
                 let layout = hideable.get_inner_mut().get_inner_mut();
                 layout.clear();
                 layout.add_child(TextView::new("Player List:"));
                 for info in &player_info {
-                    layout.add_child(TextView::new(format!("Player {}",info.id)));
+                    layout.add_child(TextView::new(info.profile.to_string()));
                 }
                 layout.add_child(DummyView);
-                layout.add_child(TextView::new("Enter Player ID"));
-                layout.add_child(Dialog::new().content(EditView::new().on_submit(move |s, id_buf| {
-                    match id_buf.parse::<u64>() {
-                        Ok(id) => {
-                            if !player_info.iter().any(|info| info.id == id) {
-                                sender.clone().send(id).unwrap();
-                                s.call_on_name("get_id", |hideable: &mut HideableView<PaddedView<LinearLayout>>| {
-                                    hideable.hide();
-                                    hideable.get_inner_mut().get_inner_mut().clear();
-                                });
-                                s.pop_layer();
-                            }
-                        },
-                        Err(_) => {},
-                    }
-                }).fixed_width(20).with_name("id")));
+                layout.add_child(TextView::new("Enter your name"));
+                layout.add_child(Dialog::new().content(EditView::new().on_submit(move |s, name| {
+                    sender.clone().send(name.trim().to_owned()).unwrap();
+                    s.call_on_name("join", |hideable: &mut HideableView<PaddedView<LinearLayout>>| {
+                        hideable.hide();
+                        hideable.get_inner_mut().get_inner_mut().clear();
+                    });
+                    s.pop_layer();
+                }).fixed_width(20).with_name("name")));
                 hideable.unhide();
             });
-            s.focus_name("id").unwrap();
+            s.focus_name("name").unwrap();
         })).map_err(|e| anyhow!("Send Error: {:?}",e))?;
 
-        let id = self.test_recv(receiver)?;
-        self.id = id;
-        Ok(id)
+        self.name = self.test_recv(receiver).await?;
+        Ok(Profile::new(&self.name))
     }
 
     async fn won(&mut self) -> Result<Ready> {
@@ -212,9 +229,9 @@ impl DurakPlayer for TuiPlayer {
                 sender.send(()).unwrap();
             });
         })).map_err(|e| anyhow!("Send Error: {:?}",e))?;
-        self.test_recv(receiver)?;
+        self.test_recv(receiver).await?;
         self.end()?;
-        println!("Congratulations, Player #{}\nYOU WON!!!", self.id);
+        println!("Congratulations, {}\nYOU WON!!!", self.name);
         Ok(Ready::Yes)
     }
 
@@ -231,9 +248,28 @@ impl DurakPlayer for TuiPlayer {
                 sender.send(()).unwrap();
             });
         })).map_err(|e| anyhow!("Send Error: {:?}",e))?;
-        self.test_recv(receiver)?;
+        self.test_recv(receiver).await?;
         self.end()?;
-        println!("I'm sorry, Player #{}\nYou lost.", self.id);
+        println!("I'm sorry, {}\nYou lost.", self.name);
+        Ok(Ready::Yes)
+    }
+
+    async fn draw(&mut self) -> Result<Ready> {
+        let (sender,receiver) = bounded::<()>(0);
+        self.tui.send(Box::new(|s: &mut Cursive| {
+            s.call_on_name("main", | hideable: &mut HideableView<LinearLayout> | {
+                hideable.hide();
+            });
+            s.call_on_name("draw", | hideable: &mut HideableView<PaddedView<LinearLayout>> | {
+                hideable.unhide();
+            });
+            s.add_global_callback(cursive::event::Key::Enter, move |_s: &mut Cursive| {
+                sender.send(()).unwrap();
+            });
+        })).map_err(|e| anyhow!("Send Error: {:?}",e))?;
+        self.test_recv(receiver).await?;
+        self.end()?;
+        println!("It's a draw, {}\nNobody lost.", self.name);
         Ok(Ready::Yes)
     }
 
@@ -256,7 +292,7 @@ impl DurakPlayer for TuiPlayer {
                 sender.send(()).unwrap();
             });
         })).map_err(|e| anyhow!("Send Error: {:?}",e))?;
-        self.test_recv(receiver)?;
+        self.test_recv(receiver).await?;
         Ok(())
     }
 
@@ -276,54 +312,150 @@ impl DurakPlayer for TuiPlayer {
                 sender.send(()).unwrap();
             });
         })).map_err(|e| anyhow!("Send Error: {:?}",e))?;
-        self.test_recv(receiver)?;
+        self.test_recv(receiver).await?;
         self.end()?;
         Ok(())
     }
 }
 
+// keeps the last few moves on screen
+fn update_move_log(siv: &mut Cursive, events: &[MoveEvent]) {
+    siv.call_on_name("move_log", |text: &mut TextView| {
+        let mut lines: Vec<String> = text.get_content().source().lines().map(str::to_owned).collect();
+        lines.extend(events.iter().map(|event| event.to_string()));
+        let start = lines.len().saturating_sub(MOVE_LOG_LEN);
+        text.set_content(lines[start..].join("\n"));
+    });
+}
+
 fn update_game_state_basic(siv: &mut Cursive, state: &ToPlayState, id: u64) {
     siv.call_on_name("player_info", |layout: &mut LinearLayout| {
         layout.clear();
-        for info in state.player_info.iter() {
-            layout.add_child(create_player_info(info,id));
+        // opponents from the one on your left to the one on your right
+        match state.player_info.iter().position(|info| info.id == id) {
+            Some(seat) => {
+                let last = state.player_info.len() - 2;
+                for (i,info) in state.others_from(seat).enumerate() {
+                    let side = match i {
+                        _ if last == 0 => None,
+                        0 => Some("left"),
+                        i if i == last => Some("right"),
+                        _ => None,
+                    };
+                    layout.add_child(create_player_info(info,id,side));
+                }
+            },
+            None => for info in state.player_info.iter() {
+                layout.add_child(create_player_info(info,id,None));
+            },
         }
     });
     siv.call_on_name("attack_cards", |layout: &mut LinearLayout| {
         layout.clear();
         layout.add_child(TextView::new("A: "));
-        for &card in state.attack_cards.iter() {
-            layout.add_child(create_card_view(card,state.trump));
+        for slot in state.table.slots() {
+            layout.add_child(create_card_view(slot.attack,state.trump));
         }
     });
     siv.call_on_name("trump_msg", |text: &mut TextView| {
-        text.set_content(format!("Trump Suit: {}",state.trump));
+        text.set_content(format!("Trump: {}    Talon: {}    Discarded: {}",state.trump_card,state.draw_pile_len,state.discard_len));
     });
     siv.call_on_name("defense_cards", |layout: &mut LinearLayout| {
         layout.clear();
         layout.add_child(TextView::new("D: "));
-        for &card in state.defense_cards.iter() {
-            layout.add_child(create_card_view(card,state.trump));
+        // keep each defense under the attack it beat
+        for slot in state.table.slots() {
+            match slot.defense {
+                Some(card) => layout.add_child(create_card_view(card,state.trump)),
+                None => layout.add_child(TextView::new(format!(" {:>4} ","-"))),
+            }
+        }
+    });
+}
+
+// cards are toggled like a pile on, then played together as one attack
+fn update_game_state_hand_dialog_attack(siv: &mut Cursive, state: &ToPlayState<'static>, id: u64, sender: Sender<Action>) {
+    siv.set_user_data(Vec::<Card>::new());
+    siv.call_on_name("hand_dialog", |dialog: &mut Dialog| {
+        dialog.clear_buttons();
+        if state.player_info[state.to_play].id == id {
+            dialog.set_title("Your Turn");
+        } else {
+            dialog.set_title(format!("{}'s turn",state.player_info[state.to_play].profile.name));
+        }
+        for &card in state.hand.iter() {
+            dialog.add_button(create_card_label(card,state.trump),move |s| {
+                if s.with_user_data(|attack_cards: &mut Vec<Card>| {
+                    attack_cards.contains(&card)
+                }).unwrap() {
+                    unset_pile_on_card(s,&card);
+                } else {
+                    set_pile_on_card(s,&card);
+                }
+            });
+        }
+        let sender2 = sender.clone();
+        let static_state = state.clone();
+        dialog.add_button("Attack", move |s| {
+            let cards = s.with_user_data(|attack_cards: &mut Vec<Card>| attack_cards.clone()).unwrap();
+            let action = Action::attack_with(cards);
+            if action != Action::Pass && static_state.validate_attack(&action).is_ok() {
+                sender2.send(action).unwrap();
+            }
+        });
+        let sender2 = sender.clone();
+        dialog.add_button("Pass", move |_s| {
+            sender2.send(Action::Pass).unwrap();
+        });
+        if state.takeback {
+            let sender2 = sender.clone();
+            dialog.add_button("Undo", move |_s| {
+                sender2.send(Action::Undo).unwrap();
+            });
         }
+        dialog.add_button("Forfeit", move |_s| {
+            sender.send(Action::Forfeit).unwrap();
+        });
+    });
+    siv.call_on_name("main", |view: &mut HideableView<LinearLayout>| view.unhide());
+    siv.focus_name("hand_dialog").unwrap();
+    siv.call_on_name("hand_dialog", |dialog: &mut Dialog| {
+        dialog.set_focus(DialogFocus::Button(0));
     });
 }
 
-fn update_game_state_hand_dialog_a_d(siv: &mut Cursive, state: &ToPlayState, id: u64, sender: Sender<Action>) {
+fn update_game_state_hand_dialog_defend(siv: &mut Cursive, state: &ToPlayState<'static>, id: u64, sender: Sender<Action>) {
     siv.call_on_name("hand_dialog", |dialog: &mut Dialog| {
         dialog.clear_buttons();
         if state.player_info[state.to_play].id == id {
             dialog.set_title("Your Turn");
         } else {
-            dialog.set_title(format!("Player {} turn",state.player_info[state.to_play].id));
+            dialog.set_title(format!("{}'s turn",state.player_info[state.to_play].profile.name));
         }
         for &card in state.hand.iter() {
             let sender2 = sender.clone();
-            dialog.add_button(create_card_label(card,state.trump), move |_s| {
-                sender2.send(Action::Play(card)).unwrap();
+            let attacks = state.beatable_attacks(&card);
+            let static_state = state.clone();
+            dialog.add_button(create_card_label(card,state.trump), move |s| {
+                match attacks.len() {
+                    0 => {},
+                    1 => { sender2.send(Action::Defend { card, against: attacks[0] }).unwrap(); },
+                    _ => { update_game_state_hand_dialog_against(s,&static_state,id,card,&attacks,sender2.clone()); },
+                }
             });
         }
+        let sender2 = sender.clone();
         dialog.add_button("Pass", move |_s| {
-            sender.send(Action::Pass).unwrap();
+            sender2.send(Action::Pass).unwrap();
+        });
+        if state.takeback {
+            let sender2 = sender.clone();
+            dialog.add_button("Undo", move |_s| {
+                sender2.send(Action::Undo).unwrap();
+            });
+        }
+        dialog.add_button("Forfeit", move |_s| {
+            sender.send(Action::Forfeit).unwrap();
         });
     });
     siv.call_on_name("main", |view: &mut HideableView<LinearLayout>| view.unhide());
@@ -333,6 +465,27 @@ fn update_game_state_hand_dialog_a_d(siv: &mut Cursive, state: &ToPlayState, id:
     });
 }
 
+// lets the defender pick which attack to beat when the card beats more than one
+fn update_game_state_hand_dialog_against(siv: &mut Cursive, state: &ToPlayState<'static>, id: u64, card: Card, attacks: &[Card], sender: Sender<Action>) {
+    siv.call_on_name("hand_dialog", |dialog: &mut Dialog| {
+        dialog.clear_buttons();
+        dialog.set_title(format!("Beat which attack with {}?",card));
+        for &against in attacks {
+            let sender2 = sender.clone();
+            dialog.add_button(create_card_label(against,state.trump), move |_s| {
+                sender2.send(Action::Defend { card, against }).unwrap();
+            });
+        }
+        let static_state = state.clone();
+        dialog.add_button("Back", move |s| {
+            update_game_state_hand_dialog_defend(s,&static_state,id,sender.clone());
+        });
+    });
+    siv.call_on_name("hand_dialog", |dialog: &mut Dialog| {
+        dialog.set_focus(DialogFocus::Button(0));
+    });
+}
+
 fn set_pile_on_card(s: &mut Cursive, card: &Card) {
     s.call_on_name("hand_dialog", |dialog: &mut Dialog| {
         for button in dialog.buttons_mut() {
@@ -366,10 +519,10 @@ fn update_game_state_hand_dialog_pile_on(siv: &mut Cursive, state: &ToPlayState,
         if state.player_info[state.to_play].id == id {
             dialog.set_title("Your Turn");
         } else {
-            dialog.set_title(format!("Player {} turn",state.player_info[state.to_play].id));
+            dialog.set_title(format!("{}'s turn",state.player_info[state.to_play].profile.name));
         }
         for &card in state.hand.iter() {
-            if state.validate_pile_on(&vec![card]).is_ok() {
+            if state.validate_pile_on(&[card]).is_ok() {
                 dialog.add_button(create_card_label(card,state.trump),move |s| {
                     if s.with_user_data(|pile_on_cards: &mut Vec<Card>| {
                         pile_on_cards.contains(&card)
@@ -396,13 +549,32 @@ fn update_game_state_hand_dialog_pile_on(siv: &mut Cursive, state: &ToPlayState,
     });
 }
 
+fn update_game_state_hand_dialog_exchange(siv: &mut Cursive, state: &ToPlayState, sender: Sender<bool>) {
+    siv.call_on_name("hand_dialog", |dialog: &mut Dialog| {
+        dialog.clear_buttons();
+        dialog.set_title(format!("Exchange your {} for the {}?",Card { rank: Rank::Six, suit: state.trump },state.trump_card));
+        let sender2 = sender.clone();
+        dialog.add_button("Yes", move |_s| {
+            sender2.send(true).unwrap();
+        });
+        dialog.add_button("No", move |_s| {
+            sender.send(false).unwrap();
+        });
+    });
+    siv.call_on_name("main", |view: &mut HideableView<LinearLayout>| view.unhide());
+    siv.focus_name("hand_dialog").unwrap();
+    siv.call_on_name("hand_dialog", |dialog: &mut Dialog| {
+        dialog.set_focus(DialogFocus::Button(0));
+    });
+}
+
 fn update_game_state_hand_dialog_observe(siv: &mut Cursive, state: &ToPlayState, id: u64) {
     siv.call_on_name("hand_dialog", |dialog: &mut Dialog| {
         dialog.clear_buttons();
         if state.player_info[state.to_play].id == id {
             dialog.set_title("Your Turn");
         } else {
-            dialog.set_title(format!("Player {} turn",state.player_info[state.to_play].id));
+            dialog.set_title(format!("{}'s turn",state.player_info[state.to_play].profile.name));
         }
         for &card in state.hand.iter() {
             dialog.add_button(create_card_label(card,state.trump), |_s| {} );
@@ -419,10 +591,11 @@ fn update_game_state_hand_dialog_observe(siv: &mut Cursive, state: &ToPlayState,
 fn setup(siv: &mut CursiveRunnable) {
     setup_msg(siv,vec!["Congratulations!","YOU WON!!!"],"won");
     setup_msg(siv,vec!["Sorry","You lost"],"lost");
+    setup_msg(siv,vec!["It's a draw","Nobody lost"],"draw");
     setup_msg(siv,vec![],"error");
     setup_msg(siv,vec![],"message");
     setup_scaffold(siv);
-    setup_id(siv);
+    setup_join(siv);
 }
 
 fn setup_msg(siv: &mut CursiveRunnable, messages: Vec<&str>, name: &str) {
@@ -441,6 +614,7 @@ fn setup_scaffold(siv: &mut CursiveRunnable) {
     let defense_cards = LinearLayout::horizontal().with_name("defense_cards");
     let trump_msg = TextView::new("").with_name("trump_msg");
     let player_info = LinearLayout::horizontal().with_name("player_info");
+    let move_log = TextView::new("").with_name("move_log");
 
     siv.add_layer(HideableView::new({
         LinearLayout::vertical()
@@ -448,24 +622,29 @@ fn setup_scaffold(siv: &mut CursiveRunnable) {
             .child(trump_msg)
             .child(attack_cards)
             .child(defense_cards)
+            .child(move_log)
             .child(hand_dialog)
     }).hidden().with_name("main"));
 }
 
-fn setup_id(siv: &mut CursiveRunnable) {
+fn setup_join(siv: &mut CursiveRunnable) {
     siv.add_layer(HideableView::new({
         PaddedView::lrtb(5,5,2,2,{
             LinearLayout::vertical()
         })
-    }).hidden().with_name("get_id"));
+    }).hidden().with_name("join"));
 }
 
-fn create_player_info(info: &PlayerInfo, id: u64) -> PaddedView<LinearLayout> {
+fn create_player_info(info: &PlayerInfo, id: u64, side: Option<&str>) -> PaddedView<LinearLayout> {
     let label = TextView::new({
-        if info.id == id {
-            format!("You")
-        } else {
-            format!("Player # {}", info.id)
+        let name = match side {
+            _ if info.id == id => "You".to_string(),
+            Some(side) => format!("{}\n({})",info.profile,side),
+            None => info.profile.to_string(),
+        };
+        match info.team {
+            Some(team) => format!("{}\nTeam {}", name, team),
+            None => name,
         }
     });
     PaddedView::lrtb(3,3,0,1,LinearLayout::vertical()
@@ -492,7 +671,6 @@ fn create_card_label(card: Card, trump: Suit) -> StyledString {
         let mut style = Style::none();
         if card.suit == trump {
             style.color = ColorStyle::front(ColorType::Palette(PaletteColor::Highlight));
-        } else {
         }
         style
     })
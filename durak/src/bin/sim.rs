@@ -0,0 +1,303 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::sync::Arc;
+
+use anyhow::{anyhow,bail,Result};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use serde::Serialize;
+
+use durak_core::game::BOT_ROUND_LIMIT;
+use durak_core::prelude::*;
+use durak_core::rating::Ratings;
+use durak_players::*;
+
+const USAGE: &str = "usage: durak-sim [--games N] [--seed S] [--players dummy,heuristic,mcts,...] [--threads T] [--attackers all|neighbours|first] [--first lowest|seat|random] [--trump-exchange] [--teams N] [--format json|csv] [--out FILE] [--ratings FILE]";
+
+#[derive(Clone,Copy,PartialEq)]
+enum Format {
+    Json,
+    Csv,
+}
+
+struct SimConfig {
+    games: usize,
+    seed: u64,
+    players: Vec<String>,
+    threads: usize,
+    rules: Rules,
+    format: Format,
+    out: Option<String>,
+    ratings: Option<String>,
+}
+
+impl SimConfig {
+    fn from_args() -> Result<Self> {
+        let mut config = SimConfig {
+            games: 1000,
+            seed: 0,
+            players: vec!["dummy".to_owned(),"heuristic".to_owned()],
+            threads: std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            rules: Rules::new(),
+            format: Format::Json,
+            out: None,
+            ratings: None,
+        };
+        let mut num_teams = None;
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| anyhow!("Missing value for {}\n{}",arg,USAGE));
+            match arg.as_str() {
+                "--games" => config.games = value()?.parse()?,
+                "--seed" => config.seed = value()?.parse()?,
+                "--players" => config.players = value()?.split(',').map(|s| s.trim().to_owned()).collect(),
+                "--threads" => config.threads = value()?.parse::<usize>()?.max(1),
+                "--attackers" => config.rules.attackers = match value()?.as_str() {
+                    "all" => AttackerRule::All,
+                    "neighbours" => AttackerRule::Neighbours,
+                    "first" => AttackerRule::FirstThenOthers,
+                    other => bail!("Unknown attacker rule {}\n{}",other,USAGE),
+                },
+                "--first" => config.rules.first_attacker = match value()?.as_str() {
+                    "lowest" => FirstAttackerRule::LowestTrump,
+                    "seat" => FirstAttackerRule::SeatOrder,
+                    "random" => FirstAttackerRule::Random,
+                    other => bail!("Unknown first attacker rule {}\n{}",other,USAGE),
+                },
+                "--trump-exchange" => config.rules.trump_exchange = true,
+                // partners sit across from each other
+                "--teams" => num_teams = Some(value()?.parse::<usize>()?),
+                "--format" => config.format = match value()?.as_str() {
+                    "json" => Format::Json,
+                    "csv" => Format::Csv,
+                    other => bail!("Unknown format {}\n{}",other,USAGE),
+                },
+                "--out" => config.out = Some(value()?),
+                "--ratings" => config.ratings = Some(value()?),
+                _ => bail!("Unknown argument {}\n{}",arg,USAGE),
+            }
+        }
+        if config.players.len() < 2 || config.players.len() > 6 {
+            bail!("Need between 2 and 6 players, got {}",config.players.len());
+        }
+        if let Some(num_teams) = num_teams {
+            if num_teams < 2 || !config.players.len().is_multiple_of(num_teams) {
+                bail!("Can't split {} players into {} teams",config.players.len(),num_teams);
+            }
+            config.rules.teams = Some((0..config.players.len()).map(|seat| seat % num_teams).collect());
+        }
+        for kind in &config.players {
+            make_bot(kind)?;
+        }
+        Ok(config)
+    }
+}
+
+fn make_bot(kind: &str) -> Result<Box<dyn DurakPlayer>> {
+    bot_by_name(kind).ok_or_else(|| anyhow!("Unknown bot {} (expected one of {})",kind,BOT_NAMES.join(", ")))
+}
+
+/// Outcome of a single simulated game.
+#[derive(Serialize)]
+struct GameRecord {
+    game: usize,
+    seed: u64,
+    trump: String,
+    rounds: usize,
+    first_attacker: usize,
+    durak_seat: Option<usize>,
+    durak_bot: Option<String>,
+    bots: Vec<String>,
+    initial_trumps: Vec<usize>,
+    finish_order: Vec<usize>,
+    losing_team: Option<usize>,
+    draw: bool,
+}
+
+#[derive(Serialize,Default)]
+struct Rate {
+    games: usize,
+    durak: usize,
+    rate: f64,
+}
+
+impl Rate {
+    fn add(&mut self, durak: bool) {
+        self.games += 1;
+        if durak { self.durak += 1; }
+        self.rate = self.durak as f64 / self.games as f64;
+    }
+}
+
+#[derive(Serialize,Default)]
+struct Summary {
+    games: usize,
+    draws: usize,
+    average_rounds: f64,
+    durak_by_seat: Vec<Rate>,
+    durak_by_bot: BTreeMap<String,Rate>,
+    durak_by_initial_trumps: BTreeMap<usize,Rate>,
+    durak_first_attacker: Rate,
+    durak_other_attackers: Rate,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    losses_by_team: BTreeMap<usize,usize>,
+    leaderboard: Vec<LeaderboardEntry>,
+}
+
+#[derive(Serialize)]
+struct LeaderboardEntry {
+    bot: String,
+    rating: f64,
+    games: u64,
+    duraks: u64,
+}
+
+impl Summary {
+    fn new(records: &[GameRecord], num_seats: usize, ratings: &Ratings) -> Self {
+        let mut summary = Summary {
+            games: records.len(),
+            durak_by_seat: (0..num_seats).map(|_| Rate::default()).collect(),
+            ..Default::default()
+        };
+        let mut total_rounds = 0;
+        for record in records {
+            total_rounds += record.rounds;
+            if record.draw { summary.draws += 1; }
+            if let Some(team) = record.losing_team {
+                *summary.losses_by_team.entry(team).or_default() += 1;
+            }
+            for seat in 0..num_seats {
+                let durak = record.durak_seat == Some(seat);
+                summary.durak_by_seat[seat].add(durak);
+                summary.durak_by_bot.entry(record.bots[seat].clone()).or_default().add(durak);
+                summary.durak_by_initial_trumps.entry(record.initial_trumps[seat]).or_default().add(durak);
+                if seat == record.first_attacker {
+                    summary.durak_first_attacker.add(durak);
+                } else {
+                    summary.durak_other_attackers.add(durak);
+                }
+            }
+        }
+        if !records.is_empty() {
+            summary.average_rounds = total_rounds as f64 / records.len() as f64;
+        }
+        summary.leaderboard = ratings.leaderboard().into_iter().map(|(name,rating)| LeaderboardEntry {
+            bot: name.to_owned(),
+            rating: rating.rating,
+            games: rating.games,
+            duraks: rating.duraks,
+        }).collect();
+        summary
+    }
+}
+
+async fn run_one(config: &SimConfig, game_ind: usize) -> Result<GameRecord> {
+    let seed = config.seed.wrapping_add(game_ind as u64);
+    let num_seats = config.players.len();
+    // rotate bots through the seats so seat and bot effects can be told apart
+    let bots: Vec<String> = (0..num_seats).map(|seat| config.players[(seat + game_ind) % num_seats].clone()).collect();
+
+    let mut game = DurakGame::new().with_rules(config.rules.clone()).with_round_limit(BOT_ROUND_LIMIT);
+    for kind in &bots {
+        game.add_player(make_bot(kind)?).await?;
+    }
+    game.init(&mut StdRng::seed_from_u64(seed))?;
+    let result = game.run_game().await?;
+    let stats = result.stats();
+
+    Ok(GameRecord {
+        game: game_ind,
+        seed,
+        trump: format!("{:?}",stats.trump),
+        rounds: stats.rounds,
+        first_attacker: stats.first_attacker,
+        durak_seat: stats.durak,
+        durak_bot: stats.durak.map(|seat| bots[seat].clone()),
+        bots,
+        initial_trumps: stats.initial_trumps.clone(),
+        finish_order: stats.finish_order.clone(),
+        losing_team: stats.losing_team,
+        draw: stats.draw,
+    })
+}
+
+async fn run_sim(config: SimConfig) -> Result<()> {
+    let config = Arc::new(config);
+    let mut set = tokio::task::JoinSet::new();
+    for worker in 0..config.threads {
+        let config = config.clone();
+        set.spawn(async move {
+            let mut records = Vec::new();
+            for game_ind in (worker..config.games).step_by(config.threads) {
+                records.push(run_one(&config,game_ind).await?);
+            }
+            Ok::<_,anyhow::Error>(records)
+        });
+    }
+    let mut records = Vec::with_capacity(config.games);
+    while let Some(res) = set.join_next().await {
+        records.append(&mut res??);
+    }
+    records.sort_by_key(|record| record.game);
+
+    let mut ratings = match &config.ratings {
+        Some(path) => Ratings::load(path)?,
+        None => Ratings::new(),
+    };
+    for record in &records {
+        let finish_order: Vec<&str> = record.finish_order.iter().map(|&seat| record.bots[seat].as_str()).collect();
+        ratings.record_game(&finish_order,record.durak_bot.as_deref());
+    }
+    if let Some(path) = &config.ratings {
+        ratings.save(path)?;
+    }
+
+    let summary = Summary::new(&records,config.players.len(),&ratings);
+    eprintln!("{}",serde_json::to_string_pretty(&summary)?);
+
+    let mut out: Box<dyn Write> = match &config.out {
+        Some(path) => Box::new(std::fs::File::create(path)?),
+        None => Box::new(std::io::stdout()),
+    };
+    match config.format {
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut out,&serde_json::json!({
+                "summary": summary,
+                "games": records,
+            }))?;
+            writeln!(out)?;
+        },
+        Format::Csv => {
+            let seats = config.players.len();
+            // finish_order lists seats separated by semicolons
+            write!(out,"game,seed,trump,rounds,first_attacker,durak_seat,durak_bot,draw,losing_team,finish_order")?;
+            for seat in 0..seats { write!(out,",bot_{},trumps_{}",seat,seat)?; }
+            writeln!(out)?;
+            for record in &records {
+                write!(out,"{},{},{},{},{},{},{},{},{},{}",
+                    record.game,
+                    record.seed,
+                    record.trump,
+                    record.rounds,
+                    record.first_attacker,
+                    record.durak_seat.map(|seat| seat.to_string()).unwrap_or_default(),
+                    record.durak_bot.clone().unwrap_or_default(),
+                    record.draw,
+                    record.losing_team.map(|team| team.to_string()).unwrap_or_default(),
+                    record.finish_order.iter().map(|seat| seat.to_string()).collect::<Vec<_>>().join(";"))?;
+                for seat in 0..seats { write!(out,",{},{}",record.bots[seat],record.initial_trumps[seat])?; }
+                writeln!(out)?;
+            }
+        },
+    }
+    out.flush()?;
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = async { run_sim(SimConfig::from_args()?).await }.await {
+        eprintln!("ERROR: {}",e);
+        std::process::exit(1);
+    }
+}
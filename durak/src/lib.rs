@@ -0,0 +1,90 @@
+//! Game setup shared by the `durak`, `durak-server` and `durak-client` binaries.
+
+use anyhow::Result;
+use rand::thread_rng;
+use tracing::{info,debug,warn,error,Level};
+use tracing_subscriber as ts;
+use tracing_appender as ta;
+
+use durak_core::prelude::*;
+use durak_core::rating::Ratings;
+use durak_players::*;
+
+/// Where the server keeps player ratings between games.
+pub const RATINGS_FILE: &str = "durak_ratings.json";
+/// The server waits for this many clients before starting.
+pub const NUM_CLIENTS: usize = 3;
+
+/// Logs to a daily file next to the crate manifest. Keep the guard for as long as logging is
+/// needed.
+pub fn init_log(prefix: &str) -> Result<ta::non_blocking::WorkerGuard> {
+    let log_dir = std::env::var("CARGO_MANIFEST_DIR")?;
+    let (file, guard) = ta::non_blocking(ta::rolling::daily(log_dir,prefix));
+    ts::fmt()
+        .with_writer(file)
+        .with_max_level(Level::DEBUG)
+        .with_env_filter({
+            ts::EnvFilter::from_default_env()
+                .add_directive("durak=debug".parse()?)
+                .add_directive("cursive=warn".parse()?)
+        }).init();
+    debug!("Log init successful");
+    Ok(guard)
+}
+
+/// Waits for [`NUM_CLIENTS`] clients, lets them choose their seats and plays a game, updating
+/// the ratings in [`RATINGS_FILE`] afterwards.
+pub async fn run_game_server() -> Result<()> {
+    let _guard = init_log("server_log").inspect_err(|_e| { warn!("Log init failed"); })?;
+    // one buggy client shouldn't end the game for everyone
+    let mut game = DurakGame::new()
+        .with_illegal_move_policy(IllegalMovePolicy::Reprompt(3))
+        .with_seating(SeatingPolicy::Chosen)
+        .with_seats(NUM_CLIENTS);
+
+    let mut server = DurakServer::new()?;
+    for _ in 0..NUM_CLIENTS {
+        server.wait_connection()?;
+        info!("Client connected to server");
+    }
+    for player in server.get_players()? {
+        game.add_player(Box::new(player)).await?;
+    }
+
+    // players are only in their seats once the game is initialized
+    game.init(&mut thread_rng()).map_err(|e| { error!("Game initialization error: {}",e); e })?;
+
+    let mut ratings = Ratings::load(RATINGS_FILE)?;
+    // names are unique within a game, so they key the ratings
+    let player_info = game.player_info();
+    let lobby = player_info.iter()
+        .map(|info| format!("{} ({:.0})",info.profile,ratings.rating(&info.profile.name)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    game.message_all(&format!("Players: {}",lobby)).await?;
+
+    let result = game.run_game().await.map_err(|e| { error!("Game error: {}",e); e })?;
+
+    let stats = result.stats();
+    let names: Vec<&str> = player_info.iter().map(|info| info.profile.name.as_str()).collect();
+    let finish_order: Vec<&str> = stats.finish_order.iter().map(|&seat| names[seat]).collect();
+    ratings.record_game(&finish_order,stats.durak.map(|seat| names[seat]));
+    ratings.save(RATINGS_FILE)?;
+
+    Ok(())
+}
+
+/// Connects to the server and plays on the command line until the game is over.
+pub async fn run_game_client() -> Result<()> {
+    let _guard = init_log("client_log").inspect_err(|_e| { warn!("Log init failed"); })?;
+    let mut player = NetClientDurakPlayer::new(CliPlayer::new(0))?;
+    info!("Connected to game server");
+    loop {
+        match player.wait().await? {
+            // won, lost or drawn
+            1 | 2 | 4 => { break; },
+            _ => {},
+        }
+    }
+    Ok(())
+}